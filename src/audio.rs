@@ -1,12 +1,38 @@
+use std::ops::Deref;
+use std::sync::mpsc::{Receiver, TryRecvError};
+
 use thiserror::Error;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Stream {
     pub id: u32,
     pub name: String,
     pub icon_name: Option<String>,
     pub volume_01: f32,
     pub mute: bool,
+    pub device_id: Option<u32>,
+    /// `application.process.binary`
+    pub binary: Option<String>,
+    /// `media.role`, e.g. "music" or "event"
+    pub role: Option<String>,
+    /// `media.name`, what the app is playing
+    pub media_name: Option<String>,
+    /// Flatpak app id, from `pipewire.access.portal.app_id` or `application.id`
+    pub app_id: Option<String>,
+    /// `application.process.id`
+    pub pid: Option<u32>,
+    pub backend_tag: BackendTag,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Device {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub volume_01: f32,
+    pub mute: bool,
+    /// The server's default output.
+    pub is_default: bool,
     pub backend_tag: BackendTag,
 }
 
@@ -16,6 +42,68 @@ pub enum BackendTag {
     PulseAudio,
 }
 
+/// Change notifications from the sound server.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackendEvent {
+    StreamAdded(u32),
+    StreamRemoved(u32),
+    StreamChanged(u32),
+    DeviceChanged(u32),
+    /// Server-wide changes such as a new default device.
+    ServerChanged,
+}
+
+/// Events from [`AudioBackend::subscribe`], read like the channel they
+/// come on. Dropping it stops whatever the backend runs to watch the
+/// server.
+pub struct Subscription {
+    rx: Receiver<BackendEvent>,
+    stop: Option<Box<dyn FnOnce() + Send>>,
+}
+
+impl Subscription {
+    /// Runs `stop` when the subscription is dropped.
+    pub fn with_stop(rx: Receiver<BackendEvent>, stop: impl FnOnce() + Send + 'static) -> Self {
+        Subscription { rx, stop: Some(Box::new(stop)) }
+    }
+}
+
+/// A subscription with nothing to stop.
+impl From<Receiver<BackendEvent>> for Subscription {
+    fn from(rx: Receiver<BackendEvent>) -> Self {
+        Subscription { rx, stop: None }
+    }
+}
+
+impl Deref for Subscription {
+    type Target = Receiver<BackendEvent>;
+
+    fn deref(&self) -> &Receiver<BackendEvent> {
+        &self.rx
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        if let Some(stop) = self.stop.take() {
+            stop();
+        }
+    }
+}
+
+/// Empties the queue without blocking. Returns whether anything was queued,
+/// or None once the sending side is gone.
+pub fn drain_events(rx: &Receiver<BackendEvent>) -> Option<bool> {
+    let mut any = false;
+    loop {
+        match rx.try_recv() {
+            Ok(_) => any = true,
+            Err(TryRecvError::Empty) => return Some(any),
+            Err(TryRecvError::Disconnected) => return None,
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum AudioError {
     #[error("backend not available")]
@@ -30,5 +118,25 @@ pub trait AudioBackend {
     fn list_streams(&self) -> Result<Vec<Stream>, AudioError>;
     fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError>;
     fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError>;
-}
 
+    // Output devices (sinks). Backends that cannot enumerate or route
+    // between devices keep these defaults.
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        Err(AudioError::NotAvailable)
+    }
+    fn set_device_volume(&self, _device_id: u32, _vol_01: f32) -> Result<(), AudioError> {
+        Err(AudioError::NotAvailable)
+    }
+    fn set_device_mute(&self, _device_id: u32, _mute: bool) -> Result<(), AudioError> {
+        Err(AudioError::NotAvailable)
+    }
+    fn move_stream(&self, _stream_id: u32, _device_id: u32) -> Result<(), AudioError> {
+        Err(AudioError::NotAvailable)
+    }
+
+    /// Starts watching the server. Events arrive until the subscription is
+    /// dropped; backends without notifications have to be polled.
+    fn subscribe(&self) -> Result<Subscription, AudioError> {
+        Err(AudioError::NotAvailable)
+    }
+}
@@ -0,0 +1,165 @@
+use std::io::Write;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use serde_json::{json, Value};
+
+use crate::audio::{AudioBackend, AudioError, Device, Stream, Subscription};
+use crate::cli::CliError;
+use crate::tr;
+use crate::values::format_percent;
+
+pub const SCROLL_STEP: f32 = 0.05;
+
+// Bursts of server events (a slider drag) produce a single update.
+const DEBOUNCE: Duration = Duration::from_millis(50);
+// Used when the backend cannot notify us of changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Waits between attempts to watch a server that went away, doubling.
+const RESUBSCRIBE_MIN: Duration = POLL_INTERVAL;
+const RESUBSCRIBE_MAX: Duration = Duration::from_secs(60);
+
+/// The server's default output, or the first one if none is marked.
+pub fn default_device(devices: &[Device]) -> Option<&Device> {
+    devices.iter().find(|d| d.is_default).or_else(|| devices.first())
+}
+
+// waybar renders tooltips as Pango markup
+fn escape_markup(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// One waybar custom-module update (`"return-type": "json"`).
+pub fn render(device: Option<&Device>, streams: &[Stream]) -> Value {
+    let muted = device.is_some_and(|d| d.mute);
+    let percentage = device.map_or(0, |d| (d.volume_01 * 100.0).round() as i32);
+    let text = match device {
+        Some(d) if d.mute => tr!("muted"),
+        Some(d) => format_percent(d.volume_01),
+        None => tr!("no output"),
+    };
+
+    let mut tooltip = Vec::new();
+    if let Some(d) = device {
+        tooltip.push(format!("{}: {}", escape_markup(&d.description), text));
+    }
+    for s in streams {
+        let volume = format_percent(s.volume_01);
+        let volume = if s.mute { tr!("{} (muted)", volume) } else { volume };
+        tooltip.push(format!("{}: {}", escape_markup(&s.name), volume));
+    }
+    if streams.is_empty() {
+        tooltip.push(tr!("No active streams"));
+    }
+
+    json!({
+        "text": text,
+        "alt": if muted { "muted" } else { "volume" },
+        "tooltip": tooltip.join("\n"),
+        "class": if muted { "muted" } else { "unmuted" },
+        "percentage": percentage,
+    })
+}
+
+fn snapshot<B: AudioBackend + ?Sized>(backend: &B) -> Result<Value, AudioError> {
+    let devices = match backend.list_devices() {
+        Err(AudioError::NotAvailable) => Vec::new(),
+        other => other?,
+    };
+    let streams = backend.list_streams()?;
+    Ok(render(default_device(&devices), &streams))
+}
+
+/// Where the bar learns of changes: server events, or polling when the
+/// backend has none. While the event stream is gone (the server
+/// restarted) the bar polls and tries to watch again, less and less often.
+struct Changes {
+    events: Option<Subscription>,
+    can_subscribe: bool,
+    backoff: Duration,
+    retry_at: Instant,
+}
+
+impl Changes {
+    fn watch<B: AudioBackend + ?Sized>(backend: &B) -> Changes {
+        let mut changes =
+            Changes { events: None, can_subscribe: true, backoff: RESUBSCRIBE_MIN, retry_at: Instant::now() };
+        changes.subscribe(backend);
+        changes
+    }
+
+    fn subscribe<B: AudioBackend + ?Sized>(&mut self, backend: &B) {
+        match backend.subscribe() {
+            Ok(rx) => self.events = Some(rx),
+            Err(AudioError::NotAvailable) => self.can_subscribe = false,
+            Err(e) => {
+                log::warn!("bar: cannot watch the server, retrying in {}s: {}", self.backoff.as_secs(), e);
+                self.retry_at = Instant::now() + self.backoff;
+                self.backoff = (self.backoff * 2).min(RESUBSCRIBE_MAX);
+            }
+        }
+    }
+
+    fn wait<B: AudioBackend + ?Sized>(&mut self, backend: &B) {
+        let Some(rx) = &self.events else {
+            thread::sleep(POLL_INTERVAL);
+            if self.can_subscribe && Instant::now() >= self.retry_at {
+                self.subscribe(backend);
+            }
+            return;
+        };
+        if rx.recv().is_err() {
+            log::warn!("bar: server event stream ended, polling until it is back");
+            self.events = None;
+            self.retry_at = Instant::now() + self.backoff;
+            self.backoff = (self.backoff * 2).min(RESUBSCRIBE_MAX);
+            return;
+        }
+        // a stream that delivers is a healthy one
+        self.backoff = RESUBSCRIBE_MIN;
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
+
+/// Prints a JSON line now and after every change, until stdout closes.
+pub fn run<B: AudioBackend + ?Sized>(backend: &B, out: &mut dyn Write) -> Result<(), CliError> {
+    let mut changes = Changes::watch(backend);
+
+    let mut last = String::new();
+    loop {
+        match snapshot(backend) {
+            Ok(value) => {
+                let line = value.to_string();
+                if line != last {
+                    writeln!(out, "{}", line)?;
+                    out.flush()?;
+                    last = line;
+                }
+            }
+            // a restarting server should not take the bar module down
+            Err(e) => log::warn!("bar: {}", e),
+        }
+        changes.wait(backend);
+    }
+}
+
+fn current_default<B: AudioBackend + ?Sized>(backend: &B) -> Result<Device, CliError> {
+    let devices = backend.list_devices()?;
+    default_device(&devices).cloned().ok_or_else(|| CliError::NoDevice("default".into()))
+}
+
+/// Scroll actions change the default output's volume. Returns the device
+/// as it is now.
+pub fn scroll<B: AudioBackend + ?Sized>(backend: &B, delta: f32) -> Result<Device, CliError> {
+    let mut d = current_default(backend)?;
+    d.volume_01 = (d.volume_01 + delta).clamp(0.0, 1.0);
+    backend.set_device_volume(d.id, d.volume_01)?;
+    Ok(d)
+}
+
+pub fn toggle_mute<B: AudioBackend + ?Sized>(backend: &B) -> Result<Device, CliError> {
+    let mut d = current_default(backend)?;
+    d.mute = !d.mute;
+    backend.set_device_mute(d.id, d.mute)?;
+    Ok(d)
+}
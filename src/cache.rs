@@ -0,0 +1,180 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+use serde_json::{json, Value};
+
+use crate::desktop::{load_entries, AppIndex, DesktopEntry};
+use crate::xdg;
+
+// Bumped whenever the layout of a cache file changes.
+const VERSION: u64 = 2;
+
+/// Modification times of the directories a cached result was read from.
+/// A directory that is missing is recorded too, so that it appearing
+/// invalidates the cache as well.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DirStamps(Vec<(PathBuf, Option<u64>)>);
+
+fn mtime_nanos(dir: &Path) -> Option<u64> {
+    let modified = fs::metadata(dir).and_then(|m| m.modified()).ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_nanos() as u64)
+}
+
+impl DirStamps {
+    pub fn of(dirs: &[PathBuf]) -> Self {
+        DirStamps(dirs.iter().map(|d| (d.clone(), mtime_nanos(d))).collect())
+    }
+
+    fn to_json(&self) -> Value {
+        Value::Array(self.0.iter().map(|(dir, mtime)| json!({ "dir": dir, "mtime": mtime })).collect())
+    }
+
+    fn from_json(v: &Value) -> Option<Self> {
+        let stamps = v.as_array()?.iter().map(|s| {
+            let dir = PathBuf::from(s.get("dir")?.as_str()?);
+            Some((dir, s.get("mtime")?.as_u64()))
+        });
+        Some(DirStamps(stamps.collect::<Option<_>>()?))
+    }
+}
+
+// The payload of a cache file, when it exists, has our version and was
+// made from directories that have not changed since.
+fn read_fresh(path: &Path, stamps: &DirStamps) -> Option<Value> {
+    let mut v: Value = serde_json::from_str(&fs::read_to_string(path).ok()?).ok()?;
+    if v.get("version")?.as_u64()? != VERSION || DirStamps::from_json(v.get("dirs")?)? != *stamps {
+        return None;
+    }
+    Some(v.get_mut("data")?.take())
+}
+
+fn write(path: &Path, stamps: &DirStamps, data: Value) -> io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let doc = json!({ "version": VERSION, "dirs": stamps.to_json(), "data": data });
+    // written aside and renamed, so a reader never sees half a file
+    let tmp = path.with_extension("tmp");
+    fs::write(&tmp, doc.to_string())?;
+    fs::rename(tmp, path)
+}
+
+fn entry_to_json(e: &DesktopEntry) -> Value {
+    json!({
+        "id": e.id,
+        "name": e.name,
+        "names": e.localized_names,
+        "icon": e.icon,
+        "wm_class": e.startup_wm_class,
+        "program": e.program,
+    })
+}
+
+fn entry_from_json(v: &Value) -> Option<DesktopEntry> {
+    let opt = |key: &str| v.get(key).and_then(Value::as_str).map(String::from);
+    Some(DesktopEntry {
+        id: opt("id")?,
+        name: opt("name")?,
+        localized_names: v
+            .get("names")?
+            .as_object()?
+            .iter()
+            .map(|(locale, name)| Some((locale.clone(), name.as_str()?.to_string())))
+            .collect::<Option<_>>()?,
+        icon: opt("icon"),
+        startup_wm_class: opt("wm_class"),
+        program: opt("program"),
+    })
+}
+
+/// `$XDG_CACHE_HOME/wlvolctl/desktop-entries.json`
+pub fn default_entries_path() -> PathBuf {
+    xdg::cache_dir().join("desktop-entries.json")
+}
+
+/// `$XDG_CACHE_HOME/wlvolctl/icons.json`
+pub fn default_icons_path() -> PathBuf {
+    xdg::cache_dir().join("icons.json")
+}
+
+/// [`load_entries`], from the cache at `path` while none of `dirs` has
+/// changed. A stale or unreadable cache is rebuilt.
+pub fn load_entries_cached(dirs: &[PathBuf], path: &Path) -> Vec<DesktopEntry> {
+    let stamps = DirStamps::of(dirs);
+    let cached = read_fresh(path, &stamps)
+        .and_then(|data| data.as_array()?.iter().map(entry_from_json).collect::<Option<Vec<_>>>());
+    if let Some(entries) = cached {
+        return entries;
+    }
+
+    let entries = load_entries(dirs);
+    let data = Value::Array(entries.iter().map(entry_to_json).collect());
+    if let Err(e) = write(path, &stamps, data) {
+        log::warn!("cache: cannot write {}: {}", path.display(), e);
+    }
+    entries
+}
+
+/// The apps of the desktop entries in `dirs`, through the default cache.
+pub fn load_app_index(dirs: &[PathBuf]) -> AppIndex {
+    AppIndex::new(load_entries_cached(dirs, &default_entries_path()))
+}
+
+/// Remembered results of an expensive lookup, such as finding an icon
+/// file, including the misses. Forgotten when one of the directories the
+/// results came from changes.
+#[derive(Debug, Clone)]
+pub struct LookupCache {
+    path: PathBuf,
+    stamps: DirStamps,
+    results: HashMap<String, Option<PathBuf>>,
+    dirty: bool,
+}
+
+impl LookupCache {
+    pub fn open(path: &Path, dirs: &[PathBuf]) -> Self {
+        let stamps = DirStamps::of(dirs);
+        let results = read_fresh(path, &stamps)
+            .and_then(|data| {
+                let map = data.as_object()?;
+                map.iter()
+                    .map(|(key, file)| Some((key.clone(), file.as_str().map(PathBuf::from))))
+                    .collect::<Option<HashMap<_, _>>>()
+            })
+            .unwrap_or_default();
+        LookupCache { path: path.to_path_buf(), stamps, results, dirty: false }
+    }
+
+    pub fn get_or_insert_with(&mut self, key: &str, lookup: impl FnOnce() -> Option<PathBuf>) -> Option<PathBuf> {
+        if let Some(found) = self.results.get(key) {
+            return found.clone();
+        }
+        let found = lookup();
+        self.results.insert(key.to_string(), found.clone());
+        self.dirty = true;
+        found
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    /// Writes the results if there are new ones.
+    pub fn save(&mut self) -> io::Result<()> {
+        if !self.dirty {
+            return Ok(());
+        }
+        let data: serde_json::Map<String, Value> =
+            self.results.iter().map(|(key, file)| (key.clone(), json!(file))).collect();
+        write(&self.path, &self.stamps, Value::Object(data))?;
+        self.dirty = false;
+        Ok(())
+    }
+}
@@ -0,0 +1,405 @@
+use std::io::{self, Write};
+use std::time::Duration;
+
+use serde_json::{json, Value};
+use thiserror::Error;
+
+use crate::audio::{AudioBackend, AudioError, Device, Stream};
+use crate::bar;
+use crate::cache;
+use crate::config::Config;
+use crate::focus;
+use crate::osd::OsdMessage;
+use crate::scenes::{Scene, SceneError, SceneStore};
+use crate::selector::{Scope, Selector, SelectorError};
+use crate::values::{format_percent, parse_percent};
+use crate::{tr, trn};
+
+pub const USAGE: &str = "\
+usage: wlvolctl [--popup [--show|--hide]]
+       wlvolctl daemon [--osd]
+       wlvolctl tray
+       wlvolctl [--json] list
+       wlvolctl [--json] get <stream>
+       wlvolctl [--json] [--osd] set <stream> <50%|+5%|-5%>
+       wlvolctl [--json] [--osd] mute|unmute|toggle <stream>
+       wlvolctl [--json] move <stream> <device>
+       wlvolctl [--json] devices
+       wlvolctl scene list | save <name> | apply <name> [--fade <ms>] | delete <name>
+       wlvolctl [--osd] bar [scroll-up [5%] | scroll-down [5%] | toggle-mute]
+
+<stream> is a selector: a stream id, an application name, or terms such as
+name~=fire, binary=mpv, role=music, pid=1234, device=hdmi, all, !role=event,
+joined with ',' (and) or '|' (or). focused-role:event matches the event
+sounds of the focused window's app (on Hyprland and sway).
+
+--popup toggles the popup of a running instance unless --show or --hide is given.
+Commands go through `wlvolctl daemon` when it is running on the session bus.
+--osd shows the new level as a desktop notification, for volume hotkeys;
+the daemon's --osd also shows changes made by other programs.
+`wlvolctl tray` shows a tray item: scroll for volume, click for the popup.
+`wlvolctl bar` prints waybar JSON lines for the default output; point the
+module's on-scroll-up/down at the bar actions and on-click at --popup.";
+
+/// [`USAGE`] in the user's language.
+pub fn usage() -> String {
+    tr!(USAGE)
+}
+
+#[derive(Error, Debug)]
+pub enum CliError {
+    #[error("{}\n\n{}", .0, usage())]
+    Usage(String),
+    #[error(transparent)]
+    Selector(#[from] SelectorError),
+    #[error("{}", tr!("no stream matches {}", format!("{:?}", .0)))]
+    NoStream(String),
+    #[error("{}", tr!("no device matches {}", format!("{:?}", .0)))]
+    NoDevice(String),
+    #[error("{}", tr!("{} matches several devices: {}", format!("{:?}", .0), .1))]
+    AmbiguousDevice(String, String),
+    #[error(transparent)]
+    Audio(#[from] AudioError),
+    #[error(transparent)]
+    Scene(#[from] SceneError),
+    #[error("{}", tr!("io error: {}", .0))]
+    Io(#[from] io::Error),
+}
+
+impl CliError {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) | CliError::Selector(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VolumeSpec {
+    Absolute(f32),
+    Relative(f32),
+}
+
+impl VolumeSpec {
+    pub fn parse(s: &str) -> Option<VolumeSpec> {
+        if let Some(rest) = s.strip_prefix('+') {
+            parse_percent(rest).map(VolumeSpec::Relative)
+        } else if let Some(rest) = s.strip_prefix('-') {
+            parse_percent(rest).map(|v| VolumeSpec::Relative(-v))
+        } else {
+            parse_percent(s).map(VolumeSpec::Absolute)
+        }
+    }
+
+    pub fn apply_to(&self, current: f32) -> f32 {
+        match *self {
+            VolumeSpec::Absolute(v) => v,
+            VolumeSpec::Relative(d) => current + d,
+        }
+        .clamp(0.0, 1.0)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MuteAction {
+    Mute,
+    Unmute,
+    Toggle,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SceneCommand {
+    List,
+    Save(String),
+    Apply { name: String, fade: Duration },
+    Delete(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BarCommand {
+    Watch,
+    Scroll(f32),
+    ToggleMute,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    List,
+    Get(String),
+    Set(String, VolumeSpec),
+    Mute(String, MuteAction),
+    Move(String, String),
+    Devices,
+    Scene(SceneCommand),
+    Bar(BarCommand),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Invocation {
+    pub command: Command,
+    pub json: bool,
+    pub osd: bool,
+}
+
+/// Parses the arguments after the program name.
+pub fn parse_args(args: &[String]) -> Result<Invocation, CliError> {
+    let json = args.iter().any(|a| a == "--json");
+    let osd = args.iter().any(|a| a == "--osd");
+    let rest: Vec<&str> = args.iter().map(String::as_str).filter(|a| !matches!(*a, "--json" | "--osd")).collect();
+    let usage = |msg: &str| CliError::Usage(msg.to_string());
+
+    // Selectors are checked up front so typos are reported as such.
+    if let [cmd, sel, ..] = rest.as_slice()
+        && matches!(*cmd, "get" | "set" | "mute" | "unmute" | "toggle" | "move")
+    {
+        Selector::parse(sel)?;
+    }
+
+    let command = match rest.as_slice() {
+        ["list"] => Command::List,
+        ["get", sel] => Command::Get(sel.to_string()),
+        ["set", sel, value] => {
+            let spec = VolumeSpec::parse(value).ok_or_else(|| usage(&tr!("bad volume {}", format!("{:?}", value))))?;
+            Command::Set(sel.to_string(), spec)
+        }
+        ["mute", sel] => Command::Mute(sel.to_string(), MuteAction::Mute),
+        ["unmute", sel] => Command::Mute(sel.to_string(), MuteAction::Unmute),
+        ["toggle", sel] => Command::Mute(sel.to_string(), MuteAction::Toggle),
+        ["move", sel, device] => Command::Move(sel.to_string(), device.to_string()),
+        ["devices"] => Command::Devices,
+        ["scene", "list"] => Command::Scene(SceneCommand::List),
+        ["scene", "save", name] => Command::Scene(SceneCommand::Save(name.to_string())),
+        ["scene", "delete", name] => Command::Scene(SceneCommand::Delete(name.to_string())),
+        ["scene", "apply", name, opts @ ..] => {
+            let fade = match opts {
+                [] => Duration::ZERO,
+                ["--fade", ms] => {
+                    Duration::from_millis(ms.parse().map_err(|_| usage(&tr!("bad fade time {}", format!("{:?}", ms))))?)
+                }
+                _ => return Err(usage(&tr!("scene apply takes only --fade <ms>"))),
+            };
+            Command::Scene(SceneCommand::Apply { name: name.to_string(), fade })
+        }
+        ["bar"] => Command::Bar(BarCommand::Watch),
+        ["bar", "toggle-mute"] => Command::Bar(BarCommand::ToggleMute),
+        ["bar", dir @ ("scroll-up" | "scroll-down"), step @ ..] => {
+            let step = match step {
+                [] => bar::SCROLL_STEP,
+                [s] => parse_percent(s).ok_or_else(|| usage(&tr!("bad step {}", format!("{:?}", s))))?,
+                _ => return Err(usage(&tr!("bar scroll takes at most one step"))),
+            };
+            Command::Bar(BarCommand::Scroll(if *dir == "scroll-up" { step } else { -step }))
+        }
+        [] => return Err(usage(&tr!("missing command"))),
+        [cmd, ..] => return Err(usage(&tr!("unknown or incomplete command {}", format!("{:?}", cmd)))),
+    };
+    Ok(Invocation { command, json, osd })
+}
+
+/// A device by id or name, or by a unique substring of name or description.
+pub fn find_device<'a>(devices: &'a [Device], query: &str) -> Result<&'a Device, CliError> {
+    if let Some(d) = query.parse::<u32>().ok().and_then(|id| devices.iter().find(|d| d.id == id)) {
+        return Ok(d);
+    }
+    if let Some(d) = devices.iter().find(|d| d.name == query) {
+        return Ok(d);
+    }
+    let q = query.to_lowercase();
+    let found: Vec<&Device> = devices
+        .iter()
+        .filter(|d| d.name.to_lowercase().contains(&q) || d.description.to_lowercase().contains(&q))
+        .collect();
+    match found.as_slice() {
+        [d] => Ok(d),
+        [] => Err(CliError::NoDevice(query.to_string())),
+        many => Err(CliError::AmbiguousDevice(
+            query.to_string(),
+            many.iter().map(|d| d.name.as_str()).collect::<Vec<_>>().join(", "),
+        )),
+    }
+}
+
+pub fn stream_json(s: &Stream) -> Value {
+    json!({
+        "id": s.id,
+        "name": s.name,
+        "volume": s.volume_01,
+        "percent": (s.volume_01 * 100.0).round() as i32,
+        "mute": s.mute,
+        "device_id": s.device_id,
+        "binary": s.binary,
+        "role": s.role,
+        "media_name": s.media_name,
+        "app_id": s.app_id,
+        "pid": s.pid,
+    })
+}
+
+pub fn device_json(d: &Device) -> Value {
+    json!({
+        "id": d.id,
+        "name": d.name,
+        "description": d.description,
+        "volume": d.volume_01,
+        "percent": (d.volume_01 * 100.0).round() as i32,
+        "mute": d.mute,
+    })
+}
+
+fn print_streams(out: &mut dyn Write, streams: &[Stream], json: bool) -> Result<(), CliError> {
+    if json {
+        let list: Vec<Value> = streams.iter().map(stream_json).collect();
+        writeln!(out, "{}", Value::Array(list))?;
+        return Ok(());
+    }
+    for s in streams {
+        let media = s.media_name.as_deref().map(|m| format!("  ({})", m)).unwrap_or_default();
+        let mute = if s.mute { "  muted" } else { "" };
+        writeln!(out, "{:>5}  {:>4}{}  {}{}", s.id, format_percent(s.volume_01), mute, s.name, media)?;
+    }
+    Ok(())
+}
+
+fn selected<B: AudioBackend + ?Sized>(backend: &B, selector: &str) -> Result<Vec<Stream>, CliError> {
+    let sel = Selector::parse(selector)?;
+    let scope = Scope {
+        devices: if sel.needs_devices() { backend.list_devices()? } else { Vec::new() },
+        focused: if sel.needs_focus() { focus::focused_window() } else { None },
+    };
+    let streams = sel.select(&backend.list_streams()?, &scope);
+    if streams.is_empty() {
+        return Err(CliError::NoStream(selector.to_string()));
+    }
+    Ok(streams)
+}
+
+// After a change, --json reports the new state of the affected streams,
+// which --osd shows as well.
+fn report_changed<B: AudioBackend + ?Sized>(
+    inv: &Invocation,
+    out: &mut dyn Write,
+    backend: &B,
+    ids: &[u32],
+) -> Result<Vec<Stream>, CliError> {
+    if !inv.json && !inv.osd {
+        return Ok(Vec::new());
+    }
+    let streams: Vec<Stream> = backend.list_streams()?.into_iter().filter(|s| ids.contains(&s.id)).collect();
+    if inv.json {
+        print_streams(out, &streams, true)?;
+    }
+    Ok(streams)
+}
+
+// --osd shows each app's icon, found through its desktop entry; the
+// entries are only read when there is something to show.
+fn stream_feedback(inv: &Invocation, changed: &[Stream]) -> Vec<OsdMessage> {
+    if !inv.osd || changed.is_empty() {
+        return Vec::new();
+    }
+    let apps = cache::load_app_index(&Config::load_default().desktop_dirs);
+    changed.iter().map(|s| OsdMessage::for_stream(s, Some(&apps))).collect()
+}
+
+/// Runs a command. Returns what to show on screen for the volumes it
+/// changed when `--osd` was given.
+pub fn run<B: AudioBackend + ?Sized>(
+    inv: &Invocation,
+    backend: &B,
+    out: &mut dyn Write,
+) -> Result<Vec<OsdMessage>, CliError> {
+    let json = inv.json;
+    let mut feedback = Vec::new();
+    match &inv.command {
+        Command::List => print_streams(out, &backend.list_streams()?, json)?,
+        Command::Get(sel) => print_streams(out, &selected(backend, sel)?, json)?,
+        Command::Set(sel, spec) => {
+            let streams = selected(backend, sel)?;
+            for s in &streams {
+                backend.set_volume(s.id, spec.apply_to(s.volume_01))?;
+            }
+            let ids: Vec<u32> = streams.iter().map(|s| s.id).collect();
+            let changed = report_changed(inv, out, backend, &ids)?;
+            feedback.extend(stream_feedback(inv, &changed));
+        }
+        Command::Mute(sel, action) => {
+            let streams = selected(backend, sel)?;
+            for s in &streams {
+                let mute = match action {
+                    MuteAction::Mute => true,
+                    MuteAction::Unmute => false,
+                    MuteAction::Toggle => !s.mute,
+                };
+                backend.set_mute(s.id, mute)?;
+            }
+            let ids: Vec<u32> = streams.iter().map(|s| s.id).collect();
+            let changed = report_changed(inv, out, backend, &ids)?;
+            feedback.extend(stream_feedback(inv, &changed));
+        }
+        Command::Move(sel, query) => {
+            let streams = selected(backend, sel)?;
+            let devices = backend.list_devices()?;
+            let device = find_device(&devices, query)?;
+            for s in &streams {
+                backend.move_stream(s.id, device.id)?;
+            }
+            let ids: Vec<u32> = streams.iter().map(|s| s.id).collect();
+            report_changed(inv, out, backend, &ids)?;
+        }
+        Command::Devices => {
+            let devices = backend.list_devices()?;
+            if json {
+                let list: Vec<Value> = devices.iter().map(device_json).collect();
+                writeln!(out, "{}", Value::Array(list))?;
+            } else {
+                for d in &devices {
+                    let mute = if d.mute { "  muted" } else { "" };
+                    writeln!(out, "{:>5}  {:>4}{}  {}  ({})", d.id, format_percent(d.volume_01), mute, d.description, d.name)?;
+                }
+            }
+        }
+        Command::Scene(cmd) => run_scene(cmd, backend, out, json)?,
+        Command::Bar(BarCommand::Watch) => bar::run(backend, out)?,
+        Command::Bar(BarCommand::Scroll(delta)) => feedback.push(OsdMessage::for_device(&bar::scroll(backend, *delta)?)),
+        Command::Bar(BarCommand::ToggleMute) => feedback.push(OsdMessage::for_device(&bar::toggle_mute(backend)?)),
+    }
+    if !inv.osd {
+        feedback.clear();
+    }
+    Ok(feedback)
+}
+
+fn run_scene<B: AudioBackend + ?Sized>(
+    cmd: &SceneCommand,
+    backend: &B,
+    out: &mut dyn Write,
+    json: bool,
+) -> Result<(), CliError> {
+    let store = SceneStore::open_default();
+    match cmd {
+        SceneCommand::List => {
+            let names = store.list()?;
+            if json {
+                writeln!(out, "{}", json!(names))?;
+            } else {
+                for name in names {
+                    writeln!(out, "{}", name)?;
+                }
+            }
+        }
+        SceneCommand::Save(name) => {
+            let scene = Scene::capture(name, backend)?;
+            store.save(&scene)?;
+            if !json {
+                let (streams, devices) = (scene.streams.len(), scene.devices.len());
+                let streams = trn!("{} stream", "{} streams", streams, streams);
+                let devices = trn!("{} device", "{} devices", devices, devices);
+                writeln!(out, "{}", tr!("Saved scene {} ({}, {})", name, streams, devices))?;
+            }
+        }
+        SceneCommand::Apply { name, fade } => store.load(name)?.apply(backend, *fade)?,
+        SceneCommand::Delete(name) => store.delete(name)?,
+    }
+    Ok(())
+}
@@ -0,0 +1,390 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ini::Ini;
+use thiserror::Error;
+
+use crate::audio::Stream;
+use crate::keys::{self, parse_chords, Action, KeyBindings};
+use crate::tr;
+use crate::values::{parse_bool, parse_duration};
+use crate::xdg;
+
+#[derive(Error, Debug)]
+pub enum ConfigError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("syntax error: {0}")]
+    Syntax(String),
+    #[error("[{section}] {key}: {msg}")]
+    Invalid { section: String, key: String, msg: String },
+    #[error("unknown section [{0}]; expected one of [general], [popup], [appearance], [paths], [apps], [aliases], [keys]")]
+    UnknownSection(String),
+    #[error("[{section}] unknown key {key:?}; expected one of {expected}")]
+    UnknownKey { section: String, key: String, expected: String },
+}
+
+/// Light or dark variant of the GTK theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Theme {
+    /// Whatever the desktop prefers.
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+/// UI settings from `$XDG_CONFIG_HOME/wlvolctl/config`:
+///
+/// ```ini
+/// [general]
+/// refresh_interval = 4s
+///
+/// [popup]
+/// min_open_time = 2000ms
+///
+/// [appearance]
+/// icon_size = 48
+/// slider_step = 0.01
+/// page_step = 0.1
+/// scroll_step = 0.05
+/// theme = system
+///
+/// [paths]
+/// desktop_dirs = /usr/share/applications:~/.local/share/applications
+/// flatpak_icon_dirs = ~/.local/share/flatpak/exports/share/icons/hicolor/scalable/apps
+///
+/// [apps]
+/// hidden = speech-dispatcher, WEBRTC VoiceEngine
+/// hide_events = yes
+/// pinned = Spotify
+/// order = firefox, mpv
+///
+/// [aliases]
+/// Firefox = Browser
+///
+/// [keys]
+/// mute = m, Ctrl+m
+/// ```
+///
+/// Every key is optional; `[keys]` is described at [`KeyBindings`].
+/// Directory lists are separated by `:`, app lists by `,`. Apps are matched
+/// by stream name, ignoring case. Pinned apps come first, then apps in
+/// `order`, then the rest as the server lists them. The running UI picks up
+/// changes to the file without a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    /// How often the stream list is re-read.
+    pub refresh_interval: Duration,
+    /// The popup ignores focus loss until it has been open this long.
+    pub popup_min_open: Duration,
+    pub icon_size: i32,
+    pub slider_step: f64,
+    /// What PageUp and PageDown change the volume by.
+    pub page_step: f64,
+    /// What one notch of the mouse wheel over a column or the tray icon
+    /// changes the volume by; touchpads scroll by fractions of it.
+    pub scroll_step: f64,
+    /// Where `.desktop` files are looked up for icons.
+    pub desktop_dirs: Vec<PathBuf>,
+    /// Directories of `<app-id>.svg` icons exported by Flatpak.
+    pub flatpak_icon_dirs: Vec<PathBuf>,
+    pub theme: Theme,
+    /// Lowercased names of apps whose streams are not shown.
+    pub hidden_apps: Vec<String>,
+    /// Hide streams with the `event` role: notification and UI sounds.
+    pub hide_events: bool,
+    /// Lowercased names of apps shown first, in this order.
+    pub pinned_apps: Vec<String>,
+    /// Lowercased app names in the order they were arranged.
+    pub app_order: Vec<String>,
+    /// Column labels by lowercased app name.
+    pub aliases: HashMap<String, String>,
+    pub keys: KeyBindings,
+}
+
+fn expand(dir: &str) -> PathBuf {
+    PathBuf::from(shellexpand::tilde(dir.trim()).to_string())
+}
+
+fn dir_list(value: &str) -> Vec<PathBuf> {
+    value.split(':').filter(|d| !d.trim().is_empty()).map(expand).collect()
+}
+
+// `$XDG_DATA_DIRS/applications`, then the entries Flatpak exports.
+fn default_desktop_dirs() -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = xdg::data_dirs().into_iter().map(|d| d.join("applications")).collect();
+    for flatpak in ["~/.local/share/flatpak/exports/share/applications", "/var/lib/flatpak/exports/share/applications"] {
+        let dir = expand(flatpak);
+        if !dirs.contains(&dir) {
+            dirs.push(dir);
+        }
+    }
+    dirs
+}
+
+fn app_list(value: &str) -> Vec<String> {
+    value.split(',').map(|a| a.trim().to_lowercase()).filter(|a| !a.is_empty()).collect()
+}
+
+/// The key streams are hidden, pinned, ordered and aliased by.
+pub fn app_key(s: &Stream) -> String {
+    s.name.to_lowercase()
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            refresh_interval: Duration::from_secs(4),
+            popup_min_open: Duration::from_millis(2000),
+            icon_size: 48,
+            slider_step: 0.01,
+            page_step: 0.1,
+            scroll_step: 0.05,
+            desktop_dirs: default_desktop_dirs(),
+            flatpak_icon_dirs: [
+                "~/.local/share/flatpak/exports/share/icons/hicolor/scalable/apps",
+                "/var/lib/flatpak/exports/share/icons/hicolor/scalable/apps",
+            ]
+            .map(expand)
+            .to_vec(),
+            theme: Theme::System,
+            hidden_apps: Vec::new(),
+            hide_events: false,
+            pinned_apps: Vec::new(),
+            app_order: Vec::new(),
+            aliases: HashMap::new(),
+            keys: KeyBindings::default(),
+        }
+    }
+}
+
+const KEYS: &[(&str, &[&str])] = &[
+    ("general", &["refresh_interval"]),
+    ("popup", &["min_open_time"]),
+    ("appearance", &["icon_size", "slider_step", "page_step", "scroll_step", "theme"]),
+    ("paths", &["desktop_dirs", "flatpak_icon_dirs"]),
+    ("apps", &["hidden", "hide_events", "pinned", "order"]),
+    ("keys", keys::ACTION_NAMES),
+];
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/wlvolctl/config`
+    pub fn default_path() -> PathBuf {
+        xdg::config_dir().join("config")
+    }
+
+    /// Loads a config file; a missing file means the defaults.
+    pub fn load(path: &Path) -> Result<Config, ConfigError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Loads the default file, reporting errors and falling back to defaults.
+    pub fn load_default() -> Config {
+        let path = Config::default_path();
+        Config::load(&path).unwrap_or_else(|e| {
+            eprintln!("{}", tr!("Ignoring config {}: {}", path.display(), e));
+            Config::default()
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Config, ConfigError> {
+        let conf = Ini::load_from_str(text).map_err(|e| ConfigError::Syntax(e.to_string()))?;
+        let mut config = Config::default();
+
+        for (section, props) in conf.iter() {
+            let Some(section) = section else {
+                if let Some((key, _)) = props.iter().next() {
+                    return Err(ConfigError::Syntax(format!("{:?} is outside of any section", key)));
+                }
+                continue;
+            };
+            // Keys here are app names, not settings.
+            if section == "aliases" {
+                for (app, label) in props.iter() {
+                    config.aliases.insert(app.to_lowercase(), label.trim().to_string());
+                }
+                continue;
+            }
+            let Some((_, known)) = KEYS.iter().find(|(name, _)| *name == section) else {
+                return Err(ConfigError::UnknownSection(section.to_string()));
+            };
+
+            for (key, value) in props.iter() {
+                let invalid = |msg: String| ConfigError::Invalid { section: section.into(), key: key.into(), msg };
+                if !known.contains(&key) {
+                    return Err(match KEYS.iter().find(|(_, keys)| keys.contains(&key)) {
+                        Some((owner, _)) => invalid(format!("belongs in [{}]", owner)),
+                        None => ConfigError::UnknownKey {
+                            section: section.into(),
+                            key: key.into(),
+                            expected: known.join(", "),
+                        },
+                    });
+                }
+
+                if section == "keys" {
+                    let action = Action::from_name(key).expect("keys are checked against KEYS");
+                    config.keys.bind(action, parse_chords(value).map_err(invalid)?);
+                    continue;
+                }
+
+                let duration = |min: Duration| match parse_duration(value) {
+                    Some(d) if d >= min => Ok(d),
+                    Some(_) => Err(invalid(format!("must be at least {}ms", min.as_millis()))),
+                    None => Err(invalid(format!("expected a duration such as 4s or 250ms, got {:?}", value))),
+                };
+                match key {
+                    "refresh_interval" => config.refresh_interval = duration(Duration::from_millis(100))?,
+                    "min_open_time" => config.popup_min_open = duration(Duration::ZERO)?,
+                    "icon_size" => {
+                        config.icon_size = match value.trim().parse::<i32>() {
+                            Ok(px @ 8..=512) => px,
+                            _ => return Err(invalid(format!("expected a size in pixels from 8 to 512, got {:?}", value))),
+                        }
+                    }
+                    "slider_step" => {
+                        config.slider_step = match value.trim().parse::<f64>() {
+                            Ok(step) if step > 0.0 && step <= 0.5 => step,
+                            _ => return Err(invalid(format!("expected a step above 0 and up to 0.5, got {:?}", value))),
+                        }
+                    }
+                    "scroll_step" => {
+                        config.scroll_step = match value.trim().parse::<f64>() {
+                            Ok(step) if step > 0.0 && step <= 0.5 => step,
+                            _ => return Err(invalid(format!("expected a step above 0 and up to 0.5, got {:?}", value))),
+                        }
+                    }
+                    "page_step" => {
+                        config.page_step = match value.trim().parse::<f64>() {
+                            Ok(step) if step > 0.0 && step <= 1.0 => step,
+                            _ => return Err(invalid(format!("expected a step above 0 and up to 1, got {:?}", value))),
+                        }
+                    }
+                    "theme" => {
+                        config.theme = match value.trim().to_lowercase().as_str() {
+                            "system" => Theme::System,
+                            "light" => Theme::Light,
+                            "dark" => Theme::Dark,
+                            _ => return Err(invalid(format!("expected system, light or dark, got {:?}", value))),
+                        }
+                    }
+                    "hidden" => config.hidden_apps = app_list(value),
+                    "hide_events" => {
+                        config.hide_events =
+                            parse_bool(value).ok_or_else(|| invalid(format!("expected yes or no, got {:?}", value)))?
+                    }
+                    "pinned" => config.pinned_apps = app_list(value),
+                    "order" => config.app_order = app_list(value),
+                    "desktop_dirs" => config.desktop_dirs = dir_list(value),
+                    "flatpak_icon_dirs" => config.flatpak_icon_dirs = dir_list(value),
+                    _ => unreachable!("keys are checked against KEYS"),
+                }
+            }
+        }
+        Ok(config)
+    }
+
+    pub fn hides(&self, s: &Stream) -> bool {
+        (self.hide_events && s.role.as_deref() == Some("event")) || self.hidden_apps.contains(&app_key(s))
+    }
+
+    pub fn is_pinned(&self, s: &Stream) -> bool {
+        self.pinned_apps.contains(&app_key(s))
+    }
+
+    pub fn alias_for(&self, s: &Stream) -> Option<&str> {
+        self.aliases.get(&app_key(s)).map(String::as_str)
+    }
+
+    /// The alias for the stream's app, or its name.
+    pub fn label_for<'a>(&'a self, s: &'a Stream) -> &'a str {
+        self.alias_for(s).unwrap_or(&s.name)
+    }
+
+    /// Puts pinned apps first, then arranged ones. Streams of one app stay
+    /// together, other streams keep their relative order.
+    pub fn sort_streams(&self, streams: &mut [Stream]) {
+        let position = |list: &[String], key: &str| list.iter().position(|a| a == key);
+        streams.sort_by_cached_key(|s| {
+            let key = app_key(s);
+            match (position(&self.pinned_apps, &key), position(&self.app_order, &key)) {
+                (Some(pin), _) => (0, pin),
+                (None, Some(pos)) => (1, pos),
+                (None, None) => (2, 0),
+            }
+        });
+    }
+
+    /// The `order` list after dragging `app` onto `target` among the apps
+    /// currently shown. Arranged apps not shown keep their place at the end.
+    pub fn moved(&self, shown: &[String], app: &str, target: &str) -> Vec<String> {
+        let to = shown.iter().position(|a| a == target).unwrap_or(shown.len());
+        let mut order: Vec<String> = shown.iter().filter(|a| *a != app).cloned().collect();
+        order.insert(to.min(order.len()), app.to_string());
+        for a in &self.app_order {
+            if !order.contains(a) {
+                order.push(a.clone());
+            }
+        }
+        order
+    }
+}
+
+/// Joins an app list the way `[apps]` keys are written.
+pub fn format_app_list(apps: &[String]) -> String {
+    apps.join(", ")
+}
+
+/// Returns `text` with `key` in `[section]` set to `value`, leaving every
+/// other line (comments included) untouched. A missing key or section is
+/// appended.
+pub fn set_value(text: &str, section: &str, key: &str, value: &str) -> String {
+    let line = format!("{} = {}", key, value);
+    let mut lines: Vec<String> = text.lines().map(str::to_string).collect();
+    let header = |l: &str| {
+        let l = l.trim();
+        (l.starts_with('[') && l.ends_with(']')).then(|| l[1..l.len() - 1].trim().to_string())
+    };
+
+    let Some(start) = lines.iter().position(|l| header(l).as_deref() == Some(section)) else {
+        if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+            lines.push(String::new());
+        }
+        lines.push(format!("[{}]", section));
+        lines.push(line);
+        return lines.join("\n") + "\n";
+    };
+    let end = lines[start + 1..].iter().position(|l| header(l).is_some()).map_or(lines.len(), |i| start + 1 + i);
+    let existing = (start + 1..end).find(|&i| lines[i].split_once('=').is_some_and(|(k, _)| k.trim() == key));
+    match existing {
+        Some(i) => lines[i] = line,
+        None => {
+            // after the section's last setting, before any blank lines
+            let last = (start..end).rev().find(|&i| !lines[i].trim().is_empty()).unwrap_or(start);
+            lines.insert(last + 1, line);
+        }
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Sets one key in the config file, creating the file if needed. A running
+/// UI picks the change up like any other edit.
+pub fn update_file(path: &Path, section: &str, key: &str, value: &str) -> Result<(), ConfigError> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, set_value(&text, section, key, value))?;
+    Ok(())
+}
@@ -0,0 +1,632 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gtk4::gio::{self, BusNameOwnerFlags, BusType, Cancellable, DBusCallFlags, DBusConnection, DBusNodeInfo, DBusSignalFlags};
+use gtk4::glib::{self, ControlFlow, Variant, VariantTy};
+use gtk4::prelude::*;
+
+use crate::audio::{drain_events, AudioBackend, AudioError, BackendEvent, BackendTag, Device, Stream, Subscription};
+use crate::cache;
+use crate::config::Config;
+use crate::desktop::AppIndex;
+use crate::limits::{CappedBackend, Limits};
+use crate::memory::VolumeMemory;
+use crate::notify::Notifier;
+use crate::osd::OsdMessage;
+use crate::pulseaudio_cli::PulseAudioCli;
+use crate::rules::{RuleEngine, RuleSet};
+use crate::tr;
+use crate::watch::watch_file;
+
+pub const BUS_NAME: &str = "org.wlvolctl.Mixer";
+pub const OBJECT_PATH: &str = "/org/wlvolctl/Mixer";
+pub const INTERFACE: &str = "org.wlvolctl.Mixer";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.wlvolctl.Mixer">
+    <method name="List">
+      <arg name="streams" type="a(usdbia{ss})" direction="out"/>
+    </method>
+    <method name="ListDevices">
+      <arg name="devices" type="a(ussdbb)" direction="out"/>
+    </method>
+    <method name="SetVolume">
+      <arg name="stream" type="u" direction="in"/>
+      <arg name="volume" type="d" direction="in"/>
+    </method>
+    <method name="SetMute">
+      <arg name="stream" type="u" direction="in"/>
+      <arg name="mute" type="b" direction="in"/>
+    </method>
+    <method name="Move">
+      <arg name="stream" type="u" direction="in"/>
+      <arg name="device" type="u" direction="in"/>
+    </method>
+    <method name="SetDeviceVolume">
+      <arg name="device" type="u" direction="in"/>
+      <arg name="volume" type="d" direction="in"/>
+    </method>
+    <method name="SetDeviceMute">
+      <arg name="device" type="u" direction="in"/>
+      <arg name="mute" type="b" direction="in"/>
+    </method>
+    <signal name="StreamAdded"><arg name="stream" type="u"/></signal>
+    <signal name="StreamRemoved"><arg name="stream" type="u"/></signal>
+    <signal name="StreamChanged"><arg name="stream" type="u"/></signal>
+    <signal name="DeviceChanged"><arg name="device" type="u"/></signal>
+  </interface>
+</node>"#;
+
+enum CallError {
+    InvalidArgs(String),
+    Failed(String),
+}
+
+impl CallError {
+    fn dbus_name(&self) -> &'static str {
+        match self {
+            CallError::InvalidArgs(_) => "org.freedesktop.DBus.Error.InvalidArgs",
+            CallError::Failed(_) => "org.wlvolctl.Mixer.Error.Failed",
+        }
+    }
+
+    fn message(&self) -> &str {
+        match self {
+            CallError::InvalidArgs(msg) | CallError::Failed(msg) => msg,
+        }
+    }
+}
+
+// How often queued server events are picked up; also the debounce window.
+const PUMP_INTERVAL: Duration = Duration::from_millis(50);
+// Used when the backend cannot notify us of changes.
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Remembered volumes are written at most this often.
+const SAVE_INTERVAL: Duration = Duration::from_secs(5);
+const CALL_TIMEOUT_MS: i32 = 5000;
+
+const SIGINT: i32 = 2;
+const SIGTERM: i32 = 15;
+
+// Wire format of a stream: id, name, volume, mute, device id (-1 for none)
+// and the optional properties by name; `hidden` = `true` marks a stream a
+// rule hides.
+type StreamTuple = (u32, String, f64, bool, i32, HashMap<String, String>);
+// id, name, description, volume, mute, default
+type DeviceTuple = (u32, String, String, f64, bool, bool);
+
+fn stream_to_tuple(s: &Stream) -> StreamTuple {
+    let mut props = HashMap::new();
+    let optional = [
+        ("icon_name", s.icon_name.clone()),
+        ("binary", s.binary.clone()),
+        ("role", s.role.clone()),
+        ("media_name", s.media_name.clone()),
+        ("app_id", s.app_id.clone()),
+        ("pid", s.pid.map(|p| p.to_string())),
+    ];
+    for (key, value) in optional {
+        if let Some(v) = value {
+            props.insert(key.to_string(), v);
+        }
+    }
+    let device = s.device_id.map_or(-1, |d| d as i32);
+    (s.id, s.name.clone(), s.volume_01 as f64, s.mute, device, props)
+}
+
+fn stream_from_tuple((id, name, volume, mute, device, mut props): StreamTuple) -> Stream {
+    Stream {
+        id,
+        name,
+        icon_name: props.remove("icon_name"),
+        volume_01: volume as f32,
+        mute,
+        device_id: u32::try_from(device).ok(),
+        binary: props.remove("binary"),
+        role: props.remove("role"),
+        media_name: props.remove("media_name"),
+        app_id: props.remove("app_id"),
+        pid: props.remove("pid").and_then(|p| p.parse().ok()),
+        // the daemon always drives the server through pactl
+        backend_tag: BackendTag::PulseAudio,
+    }
+}
+
+fn device_to_tuple(d: &Device) -> DeviceTuple {
+    (d.id, d.name.clone(), d.description.clone(), d.volume_01 as f64, d.mute, d.is_default)
+}
+
+fn device_from_tuple((id, name, description, volume, mute, is_default): DeviceTuple) -> Device {
+    Device { id, name, description, volume_01: volume as f32, mute, is_default, backend_tag: BackendTag::PulseAudio }
+}
+
+// No `LockingBackend`: the bus has no way to lock a stream, locks belong to
+// the UI that set them.
+type DaemonBackend = CappedBackend<PulseAudioCli>;
+
+/// State owned by the daemon: the backend, the rules and the remembered
+/// volumes, plus the last listing to tell what changed.
+struct Mixer {
+    backend: DaemonBackend,
+    rules: RuleEngine,
+    memory: VolumeMemory,
+    memory_dirty: bool,
+    saved_at: Instant,
+    streams: HashMap<u32, Stream>,
+    devices: HashMap<u32, Device>,
+    primed: bool,
+    osd: Option<Notifier>,
+    // for the OSD's app icons
+    osd_apps: Option<AppIndex>,
+}
+
+impl Mixer {
+    fn new(osd: bool) -> Self {
+        let backend = CappedBackend::new(PulseAudioCli, Limits::load_default());
+        let path = RuleSet::default_path();
+        let rules = RuleSet::load(&path).unwrap_or_else(|e| {
+            eprintln!("{}", tr!("Ignoring rules in {}: {}", path.display(), e));
+            RuleSet::default()
+        });
+        let path = VolumeMemory::default_path();
+        let memory = VolumeMemory::load(&path).unwrap_or_else(|e| {
+            eprintln!("{}", tr!("Ignoring remembered volumes in {}: {}", path.display(), e));
+            VolumeMemory::default()
+        });
+        Mixer {
+            backend,
+            rules: RuleEngine::new(rules),
+            memory,
+            memory_dirty: false,
+            saved_at: Instant::now(),
+            streams: HashMap::new(),
+            devices: HashMap::new(),
+            primed: false,
+            osd: if osd { Notifier::connect() } else { None },
+            osd_apps: osd.then(|| cache::load_app_index(&Config::load_default().desktop_dirs)),
+        }
+    }
+
+    // Rules win over remembered volumes.
+    fn restore(&self, s: &Stream) {
+        let actions = self.rules.actions_for(s);
+        let Some(r) = self.memory.recall(s) else { return };
+        if actions.volume.is_none() && (r.volume_01 - s.volume_01).abs() > 0.005 {
+            log::info!("daemon: restoring {} (#{}) to {:.2}", s.name, s.id, r.volume_01);
+            if let Err(e) = self.backend.set_volume(s.id, r.volume_01) {
+                log::warn!("daemon: failed to restore volume of {}: {}", s.name, e);
+            }
+        }
+        if actions.mute.is_none() && r.mute != s.mute
+            && let Err(e) = self.backend.set_mute(s.id, r.mute)
+        {
+            log::warn!("daemon: failed to restore mute of {}: {}", s.name, e);
+        }
+    }
+
+    /// Re-reads the server state, handles new streams and returns what
+    /// changed since the last call.
+    fn refresh(&mut self) -> Result<Vec<BackendEvent>, AudioError> {
+        let streams = self.backend.list_streams()?;
+        self.rules.process(&streams, &self.backend);
+
+        let mut events = Vec::new();
+        for s in &streams {
+            match self.streams.get(&s.id) {
+                None => {
+                    // A new stream is remembered from the next listing on,
+                    // once rules and restored values have taken effect.
+                    if self.primed {
+                        self.restore(s);
+                    } else {
+                        self.memory_dirty |= self.memory.remember(s);
+                    }
+                    events.push(BackendEvent::StreamAdded(s.id));
+                }
+                Some(old) => {
+                    if old != s {
+                        events.push(BackendEvent::StreamChanged(s.id));
+                    }
+                    if let Some(osd) = &self.osd
+                        && (old.volume_01 != s.volume_01 || old.mute != s.mute)
+                    {
+                        osd.show(&OsdMessage::for_stream(s, self.osd_apps.as_ref()));
+                    }
+                    self.memory_dirty |= self.memory.remember(s);
+                }
+            }
+        }
+        for id in self.streams.keys() {
+            if !streams.iter().any(|s| s.id == *id) {
+                events.push(BackendEvent::StreamRemoved(*id));
+            }
+        }
+        self.streams = streams.into_iter().map(|s| (s.id, s)).collect();
+
+        let devices = match self.backend.list_devices() {
+            Err(AudioError::NotAvailable) => Vec::new(),
+            other => other?,
+        };
+        for d in &devices {
+            if self.devices.get(&d.id) != Some(d) {
+                events.push(BackendEvent::DeviceChanged(d.id));
+            }
+        }
+        self.devices = devices.into_iter().map(|d| (d.id, d)).collect();
+
+        self.primed = true;
+        Ok(events)
+    }
+
+    /// Swaps in the rules file after an edit, keeping the current rules if
+    /// it does not parse. New rules apply to streams that start afterwards.
+    fn reload_rules(&mut self) {
+        let path = RuleSet::default_path();
+        match RuleSet::load(&path) {
+            Ok(rules) => {
+                log::info!("daemon: reloaded rules from {}", path.display());
+                self.rules.set_rules(rules);
+                // re-evaluate hiding; the engine leaves running streams alone
+                let streams: Vec<Stream> = self.streams.values().cloned().collect();
+                self.rules.process(&streams, &self.backend);
+            }
+            Err(e) => eprintln!("{}", tr!("Keeping the previous rules, {} is invalid: {}", path.display(), e)),
+        }
+    }
+
+    // Streams above a new cap are pulled down on the next listing.
+    fn reload_limits(&self) {
+        let path = Limits::default_path();
+        match Limits::load(&path) {
+            Ok(limits) => {
+                log::info!("daemon: reloaded limits from {}", path.display());
+                self.backend.set_limits(limits);
+            }
+            Err(e) => eprintln!("{}", tr!("Keeping the previous limits, {} is invalid: {}", path.display(), e)),
+        }
+    }
+
+    fn save_memory(&mut self, force: bool) {
+        if !self.memory_dirty || (!force && self.saved_at.elapsed() < SAVE_INTERVAL) {
+            return;
+        }
+        if let Err(e) = self.memory.save(&VolumeMemory::default_path()) {
+            log::warn!("daemon: failed to save remembered volumes: {}", e);
+        }
+        self.memory_dirty = false;
+        self.saved_at = Instant::now();
+    }
+
+    fn call(&self, method: &str, params: &Variant) -> Result<Option<Variant>, CallError> {
+        let bad_args = || CallError::InvalidArgs(format!("bad arguments for {}: {}", method, params.type_()));
+        let done = |r: Result<(), AudioError>| r.map(|_| None).map_err(|e| CallError::Failed(e.to_string()));
+        match method {
+            "List" => {
+                let mut streams: Vec<&Stream> = self.streams.values().collect();
+                streams.sort_by_key(|s| s.id);
+                // rule-hidden streams stay listed, so the CLI can still
+                // target them; front ends skip the ones flagged
+                let list: Vec<StreamTuple> = streams
+                    .into_iter()
+                    .map(|s| {
+                        let mut tuple = stream_to_tuple(s);
+                        if self.rules.is_hidden(s.id) {
+                            tuple.5.insert("hidden".to_string(), "true".to_string());
+                        }
+                        tuple
+                    })
+                    .collect();
+                Ok(Some((list,).to_variant()))
+            }
+            "ListDevices" => {
+                let mut devices: Vec<&Device> = self.devices.values().collect();
+                devices.sort_by_key(|d| d.id);
+                let list: Vec<DeviceTuple> = devices.into_iter().map(device_to_tuple).collect();
+                Ok(Some((list,).to_variant()))
+            }
+            "SetVolume" => {
+                let (id, vol) = params.get::<(u32, f64)>().ok_or_else(bad_args)?;
+                done(self.backend.set_volume(id, (vol as f32).clamp(0.0, 1.0)))
+            }
+            "SetMute" => {
+                let (id, mute) = params.get::<(u32, bool)>().ok_or_else(bad_args)?;
+                done(self.backend.set_mute(id, mute))
+            }
+            "Move" => {
+                let (id, device) = params.get::<(u32, u32)>().ok_or_else(bad_args)?;
+                done(self.backend.move_stream(id, device))
+            }
+            "SetDeviceVolume" => {
+                let (id, vol) = params.get::<(u32, f64)>().ok_or_else(bad_args)?;
+                done(self.backend.set_device_volume(id, (vol as f32).clamp(0.0, 1.0)))
+            }
+            "SetDeviceMute" => {
+                let (id, mute) = params.get::<(u32, bool)>().ok_or_else(bad_args)?;
+                done(self.backend.set_device_mute(id, mute))
+            }
+            _ => Err(CallError::Failed(format!("unknown method {}", method))),
+        }
+    }
+}
+
+fn emit(conn: &DBusConnection, event: BackendEvent) {
+    let (signal, id) = match event {
+        BackendEvent::StreamAdded(id) => ("StreamAdded", id),
+        BackendEvent::StreamRemoved(id) => ("StreamRemoved", id),
+        BackendEvent::StreamChanged(id) => ("StreamChanged", id),
+        BackendEvent::DeviceChanged(id) => ("DeviceChanged", id),
+        BackendEvent::ServerChanged => return,
+    };
+    if let Err(e) = conn.emit_signal(None, OBJECT_PATH, INTERFACE, signal, Some(&(id,).to_variant())) {
+        log::warn!("daemon: failed to emit {}: {}", signal, e);
+    }
+}
+
+fn refresh_and_emit(conn: &DBusConnection, mixer: &RefCell<Mixer>) {
+    let mut mixer = mixer.borrow_mut();
+    match mixer.refresh() {
+        Ok(events) => events.into_iter().for_each(|e| emit(conn, e)),
+        Err(e) => log::warn!("daemon: {}", e),
+    }
+    mixer.save_memory(false);
+}
+
+fn register(conn: &DBusConnection, mixer: Rc<RefCell<Mixer>>) -> Result<(), glib::Error> {
+    let node = DBusNodeInfo::for_xml(INTROSPECTION)?;
+    let iface = node.lookup_interface(INTERFACE).expect("interface in introspection data");
+    conn.register_object(
+        OBJECT_PATH,
+        &iface,
+        move |conn, _sender, _path, _iface, method, params, invocation| {
+            let result = mixer.borrow().call(method, &params);
+            match result {
+                Ok(reply) => {
+                    invocation.return_value(reply.as_ref());
+                    // changes are reported to everyone, not only the caller
+                    if !matches!(method, "List" | "ListDevices") {
+                        refresh_and_emit(&conn, &mixer);
+                    }
+                }
+                Err(e) => invocation.return_dbus_error(e.dbus_name(), e.message()),
+            }
+        },
+        |_, _, _, _, _| unreachable!("the interface has no properties"),
+        |_, _, _, _, _, _| false,
+    )?;
+    Ok(())
+}
+
+// Server events arrive on a channel fed by a reader thread; they are
+// drained on the main loop, falling back to polling when there are none.
+fn start_event_pump(conn: DBusConnection, mixer: Rc<RefCell<Mixer>>) {
+    let mut events = match mixer.borrow().backend.subscribe() {
+        Ok(rx) => Some(rx),
+        Err(e) => {
+            log::warn!("daemon: no server events ({}), polling instead", e);
+            None
+        }
+    };
+    let mut last_refresh = Instant::now();
+
+    glib::timeout_add_local(PUMP_INTERVAL, move || {
+        let due = match &events {
+            Some(rx) => drain_events(rx).unwrap_or_else(|| {
+                log::warn!("daemon: server event stream ended, polling instead");
+                events = None;
+                true
+            }),
+            None => last_refresh.elapsed() >= POLL_INTERVAL,
+        };
+        if due {
+            refresh_and_emit(&conn, &mixer);
+            last_refresh = Instant::now();
+        } else {
+            mixer.borrow_mut().save_memory(false);
+        }
+        ControlFlow::Continue
+    });
+}
+
+/// Runs `wlvolctl daemon` until interrupted. Returns the exit code. With
+/// `osd`, every stream volume change it sees is shown on screen, whoever
+/// made it.
+pub fn run_daemon(osd: bool) -> i32 {
+    let main_loop = glib::MainLoop::new(None, false);
+    let mixer = Rc::new(RefCell::new(Mixer::new(osd)));
+    let exit_code = Rc::new(Cell::new(0));
+
+    if let Err(e) = mixer.borrow_mut().refresh() {
+        log::warn!("daemon: {}", e);
+    }
+
+    let owner = gio::bus_own_name(
+        BusType::Session,
+        BUS_NAME,
+        BusNameOwnerFlags::NONE,
+        {
+            let mixer = mixer.clone();
+            let main_loop = main_loop.clone();
+            let exit_code = exit_code.clone();
+            move |conn, _| {
+                if let Err(e) = register(&conn, mixer.clone()) {
+                    eprintln!("wlvolctl: {}", tr!("cannot export {}: {}", OBJECT_PATH, e));
+                    exit_code.set(1);
+                    main_loop.quit();
+                    return;
+                }
+                start_event_pump(conn, mixer.clone());
+            }
+        },
+        |_, name| log::info!("daemon: serving {}", name),
+        {
+            let main_loop = main_loop.clone();
+            let exit_code = exit_code.clone();
+            move |_, name| {
+                eprintln!("wlvolctl: {}", tr!("cannot own {} on the session bus; is a daemon already running?", name));
+                exit_code.set(1);
+                main_loop.quit();
+            }
+        },
+    );
+
+    let rules_monitor = watch_file(&RuleSet::default_path(), {
+        let mixer = mixer.clone();
+        move || mixer.borrow_mut().reload_rules()
+    });
+    let limits_monitor = watch_file(&Limits::default_path(), {
+        let mixer = mixer.clone();
+        move || mixer.borrow().reload_limits()
+    });
+
+    for signum in [SIGINT, SIGTERM] {
+        let main_loop = main_loop.clone();
+        glib::unix_signal_add_local(signum, move || {
+            main_loop.quit();
+            ControlFlow::Break
+        });
+    }
+
+    main_loop.run();
+    drop(rules_monitor);
+    drop(limits_monitor);
+    gio::bus_unown_name(owner);
+    mixer.borrow_mut().save_memory(true);
+    exit_code.get()
+}
+
+/// [`AudioBackend`] that forwards to a running `wlvolctl daemon`.
+pub struct DaemonClient {
+    conn: DBusConnection,
+}
+
+impl DaemonClient {
+    /// Connects if a daemon currently owns the bus name.
+    pub fn connect() -> Option<DaemonClient> {
+        let conn = gio::bus_get_sync(BusType::Session, Cancellable::NONE).ok()?;
+        let reply = conn
+            .call_sync(
+                Some("org.freedesktop.DBus"),
+                "/org/freedesktop/DBus",
+                "org.freedesktop.DBus",
+                "NameHasOwner",
+                Some(&(BUS_NAME,).to_variant()),
+                Some(VariantTy::new("(b)").unwrap()),
+                DBusCallFlags::NONE,
+                CALL_TIMEOUT_MS,
+                Cancellable::NONE,
+            )
+            .ok()?;
+        let (running,) = reply.get::<(bool,)>()?;
+        running.then_some(DaemonClient { conn })
+    }
+
+    fn call(&self, method: &str, params: Option<Variant>) -> Result<Variant, AudioError> {
+        self.conn
+            .call_sync(
+                Some(BUS_NAME),
+                OBJECT_PATH,
+                INTERFACE,
+                method,
+                params.as_ref(),
+                None,
+                DBusCallFlags::NONE,
+                CALL_TIMEOUT_MS,
+                Cancellable::NONE,
+            )
+            .map_err(|e| AudioError::CommandFailed(format!("{}: {}", method, e.message())))
+    }
+
+    fn call_unit(&self, method: &str, params: Variant) -> Result<(), AudioError> {
+        self.call(method, Some(params)).map(|_| ())
+    }
+}
+
+impl AudioBackend for DaemonClient {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        let reply = self.call("List", None)?;
+        let (list,) = reply
+            .get::<(Vec<StreamTuple>,)>()
+            .ok_or_else(|| AudioError::ParseError(format!("unexpected reply {}", reply.type_())))?;
+        Ok(list.into_iter().map(stream_from_tuple).collect())
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.call_unit("SetVolume", (stream_id, vol_01 as f64).to_variant())
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.call_unit("SetMute", (stream_id, mute).to_variant())
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        let reply = self.call("ListDevices", None)?;
+        let (list,) = reply
+            .get::<(Vec<DeviceTuple>,)>()
+            .ok_or_else(|| AudioError::ParseError(format!("unexpected reply {}", reply.type_())))?;
+        Ok(list.into_iter().map(device_from_tuple).collect())
+    }
+
+    fn set_device_volume(&self, device_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.call_unit("SetDeviceVolume", (device_id, vol_01 as f64).to_variant())
+    }
+
+    fn set_device_mute(&self, device_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.call_unit("SetDeviceMute", (device_id, mute).to_variant())
+    }
+
+    fn move_stream(&self, stream_id: u32, device_id: u32) -> Result<(), AudioError> {
+        self.call_unit("Move", (stream_id, device_id).to_variant())
+    }
+
+    // Signals are dispatched on a private main loop in a helper thread,
+    // which the subscription quits when dropped. Quitting goes through
+    // the loop's context, so it also works before the loop runs.
+    fn subscribe(&self) -> Result<Subscription, AudioError> {
+        let (tx, rx) = mpsc::channel();
+        let conn = self.conn.clone();
+        let ctx = glib::MainContext::new();
+        let main_loop = glib::MainLoop::new(Some(&ctx), false);
+        let stop = {
+            let ctx = ctx.clone();
+            let main_loop = main_loop.clone();
+            move || ctx.invoke(move || main_loop.quit())
+        };
+        thread::spawn(move || {
+            let result = ctx.with_thread_default(|| {
+                let quit = main_loop.clone();
+                let id = conn.signal_subscribe(
+                    Some(BUS_NAME),
+                    Some(INTERFACE),
+                    None,
+                    Some(OBJECT_PATH),
+                    None,
+                    DBusSignalFlags::NONE,
+                    move |_, _, _, _, signal, params| {
+                        let Some((id,)) = params.get::<(u32,)>() else { return };
+                        let event = match signal {
+                            "StreamAdded" => BackendEvent::StreamAdded(id),
+                            "StreamRemoved" => BackendEvent::StreamRemoved(id),
+                            "StreamChanged" => BackendEvent::StreamChanged(id),
+                            "DeviceChanged" => BackendEvent::DeviceChanged(id),
+                            _ => return,
+                        };
+                        if tx.send(event).is_err() {
+                            quit.quit();
+                        }
+                    },
+                );
+                main_loop.run();
+                conn.signal_unsubscribe(id);
+            });
+            if let Err(e) = result {
+                log::warn!("daemon client: cannot watch signals: {}", e);
+            }
+        });
+        Ok(Subscription::with_stop(rx, stop))
+    }
+}
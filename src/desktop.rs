@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ini::{Ini, ParseOption};
+
+use crate::audio::Stream;
+
+/// The parts of a `.desktop` file used to recognise an app's streams.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DesktopEntry {
+    /// The desktop file id: its name without `.desktop`, e.g.
+    /// `org.mozilla.firefox`. Flatpak apps use their app id.
+    pub id: String,
+    pub name: String,
+    /// `Name[xx]` translations by locale key, e.g. `de` or `pt_BR`.
+    pub localized_names: HashMap<String, String>,
+    pub icon: Option<String>,
+    pub startup_wm_class: Option<String>,
+    /// File name of the program `Exec` starts, e.g. `firefox`.
+    pub program: Option<String>,
+}
+
+// The program behind an Exec line, skipping `env` and its assignments.
+fn exec_program(exec: &str) -> Option<String> {
+    exec.split_whitespace()
+        .map(|word| word.trim_matches('"'))
+        .find(|word| *word != "env" && !word.contains('='))
+        .and_then(|program| Path::new(program).file_name())
+        .map(|name| name.to_string_lossy().to_string())
+}
+
+impl DesktopEntry {
+    /// Reads a desktop file's text. Hidden entries and entries without a
+    /// name are skipped.
+    pub fn parse(id: &str, text: &str) -> Option<DesktopEntry> {
+        // Exec lines carry their own quoting and backslashes
+        let option = ParseOption { enabled_quote: false, enabled_escape: false, ..ParseOption::default() };
+        let conf = Ini::load_from_str_opt(text, option).ok()?;
+        let section = conf.section(Some("Desktop Entry"))?;
+        if section.get("Hidden").is_some_and(|v| v.trim() == "true") {
+            return None;
+        }
+        let get = |key: &str| section.get(key).map(str::trim).filter(|v| !v.is_empty()).map(String::from);
+        let localized_names = section
+            .iter()
+            .filter_map(|(key, value)| {
+                let locale = key.strip_prefix("Name[")?.strip_suffix(']')?;
+                let value = value.trim();
+                (!value.is_empty()).then(|| (locale.to_string(), value.to_string()))
+            })
+            .collect();
+        Some(DesktopEntry {
+            id: id.to_string(),
+            name: get("Name")?,
+            localized_names,
+            icon: get("Icon"),
+            startup_wm_class: get("StartupWMClass"),
+            program: section.get("Exec").and_then(exec_program),
+        })
+    }
+}
+
+impl DesktopEntry {
+    /// The name for the first of `locale_keys` the entry has a translation
+    /// for, else the untranslated name.
+    pub fn name_for(&self, locale_keys: &[String]) -> &str {
+        locale_keys.iter().find_map(|key| self.localized_names.get(key)).unwrap_or(&self.name)
+    }
+}
+
+// The keys to try for one locale, most specific first, as the Desktop
+// Entry spec lists them. The encoding plays no part.
+fn locale_variants(locale: &str) -> Vec<String> {
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split('.').next().unwrap_or(rest);
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    let mut keys = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        keys.push(format!("{}_{}@{}", lang, country, modifier));
+    }
+    if let Some(country) = country {
+        keys.push(format!("{}_{}", lang, country));
+    }
+    if let Some(modifier) = modifier {
+        keys.push(format!("{}@{}", lang, modifier));
+    }
+    keys.push(lang.to_string());
+    keys
+}
+
+/// Locale keys for `Name[...]` lookups, in order of preference, from the
+/// environment as gettext reads it: `LANGUAGE` may list several locales,
+/// but only counts when `LC_ALL`, `LC_MESSAGES` or `LANG` selects a locale
+/// other than `C`.
+pub fn locale_keys(var: impl Fn(&str) -> Option<String>) -> Vec<String> {
+    let set = |name: &str| var(name).filter(|v| !v.is_empty());
+    let Some(locale) = set("LC_ALL").or_else(|| set("LC_MESSAGES")).or_else(|| set("LANG")) else {
+        return Vec::new();
+    };
+    if locale == "C" || locale == "POSIX" || locale.starts_with("C.") {
+        return Vec::new();
+    }
+
+    let preferred = set("LANGUAGE").unwrap_or_else(|| locale.clone());
+    let mut keys: Vec<String> = Vec::new();
+    for variant in preferred.split(':').filter(|l| !l.is_empty()).flat_map(locale_variants) {
+        if !keys.contains(&variant) {
+            keys.push(variant);
+        }
+    }
+    keys
+}
+
+/// [`locale_keys`] for this process.
+pub fn user_locale_keys() -> Vec<String> {
+    locale_keys(|name| std::env::var(name).ok())
+}
+
+/// Reads every `.desktop` file in `dirs`. When several directories hold
+/// the same id, the first one wins, as with `$XDG_DATA_DIRS`.
+pub fn load_entries(dirs: &[PathBuf]) -> Vec<DesktopEntry> {
+    let mut entries: Vec<DesktopEntry> = Vec::new();
+    for dir in dirs {
+        let Ok(files) = fs::read_dir(dir) else { continue };
+        for file in files.flatten() {
+            let path = file.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("desktop") {
+                continue;
+            }
+            let Some(id) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else { continue };
+            if entries.iter().any(|e| e.id == id) {
+                continue;
+            }
+            match fs::read_to_string(&path) {
+                Ok(text) => entries.extend(DesktopEntry::parse(&id, &text)),
+                Err(e) => log::debug!("desktop: cannot read {}: {}", path.display(), e),
+            }
+        }
+    }
+    entries
+}
+
+/// Desktop entries indexed by everything a stream can be matched on.
+/// Keys are lowercased.
+#[derive(Debug, Clone, Default)]
+pub struct AppIndex {
+    entries: Vec<DesktopEntry>,
+    by_id: HashMap<String, usize>,
+    by_wm_class: HashMap<String, usize>,
+    by_program: HashMap<String, usize>,
+    by_name: HashMap<String, usize>,
+}
+
+impl AppIndex {
+    pub fn new(entries: Vec<DesktopEntry>) -> Self {
+        let mut index = AppIndex::default();
+        for (i, e) in entries.iter().enumerate() {
+            let add = |map: &mut HashMap<String, usize>, key: &str| {
+                map.entry(key.to_lowercase()).or_insert(i);
+            };
+            add(&mut index.by_id, &e.id);
+            // the last part of a reverse-DNS id: org.mozilla.firefox → firefox
+            if let Some((_, last)) = e.id.rsplit_once('.') {
+                add(&mut index.by_id, last);
+            }
+            if let Some(class) = &e.startup_wm_class {
+                add(&mut index.by_wm_class, class);
+            }
+            if let Some(program) = &e.program {
+                add(&mut index.by_program, program);
+            }
+            add(&mut index.by_name, &e.name);
+        }
+        index.entries = entries;
+        index
+    }
+
+    pub fn entries(&self) -> &[DesktopEntry] {
+        &self.entries
+    }
+
+    /// The entry of the app playing `s`: by Flatpak id, then by binary
+    /// (against `Exec`, `StartupWMClass` and the desktop file id), and
+    /// only then by display name.
+    pub fn entry_for(&self, s: &Stream) -> Option<&DesktopEntry> {
+        let find = |map: &HashMap<String, usize>, key: &str| map.get(&key.to_lowercase()).copied();
+        let by_app_id = s.app_id.as_deref().and_then(|id| find(&self.by_id, id));
+        let by_binary = || {
+            let binary = s.binary.as_deref()?;
+            find(&self.by_program, binary)
+                .or_else(|| find(&self.by_wm_class, binary))
+                .or_else(|| find(&self.by_id, binary))
+        };
+        let by_name = || find(&self.by_wm_class, &s.name).or_else(|| find(&self.by_name, &s.name));
+        by_app_id.or_else(by_binary).or_else(by_name).map(|i| &self.entries[i])
+    }
+
+    /// The icon for `s`: what the stream itself names, else its app's
+    /// desktop entry icon, else the Flatpak app id (Flatpak exports icons
+    /// under that name).
+    pub fn icon_for(&self, s: &Stream) -> Option<String> {
+        s.icon_name
+            .clone()
+            .or_else(|| self.entry_for(s).and_then(|e| e.icon.clone()))
+            .or_else(|| s.app_id.clone())
+    }
+}
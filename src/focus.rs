@@ -0,0 +1,84 @@
+use std::env;
+use std::process::Command;
+
+use serde_json::Value;
+
+use crate::audio::Stream;
+
+/// The window with the keyboard focus, as the compositor reports it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FocusedWindow {
+    /// Wayland app id, or X11 class for Xwayland windows.
+    pub app_id: Option<String>,
+    pub pid: Option<u32>,
+}
+
+impl FocusedWindow {
+    /// Whether `s` is played by the focused window's app: same process,
+    /// or an app id matching the stream's Flatpak id, binary or name.
+    pub fn owns(&self, s: &Stream) -> bool {
+        if self.pid.is_some() && self.pid == s.pid {
+            return true;
+        }
+        let Some(app_id) = &self.app_id else { return false };
+        [s.app_id.as_deref(), s.binary.as_deref(), Some(s.name.as_str())]
+            .into_iter()
+            .flatten()
+            .any(|v| v.eq_ignore_ascii_case(app_id))
+    }
+}
+
+/// Parses `hyprctl activewindow -j`; no window gives `{}`.
+pub fn parse_hyprland(json: &str) -> Option<FocusedWindow> {
+    let v: Value = serde_json::from_str(json).ok()?;
+    let window = FocusedWindow {
+        app_id: v["class"].as_str().filter(|c| !c.is_empty()).map(str::to_string),
+        pid: v["pid"].as_u64().and_then(|p| u32::try_from(p).ok()),
+    };
+    (window != FocusedWindow::default()).then_some(window)
+}
+
+/// Parses `swaymsg -t get_tree`, looking for the focused node.
+pub fn parse_sway(json: &str) -> Option<FocusedWindow> {
+    fn find(node: &Value) -> Option<&Value> {
+        if node["focused"].as_bool() == Some(true) {
+            return Some(node);
+        }
+        ["nodes", "floating_nodes"]
+            .iter()
+            .filter_map(|key| node[key].as_array())
+            .flatten()
+            .find_map(find)
+    }
+    let v: Value = serde_json::from_str(json).ok()?;
+    let node = find(&v)?;
+    let app_id = node["app_id"].as_str().or_else(|| node["window_properties"]["class"].as_str());
+    let window = FocusedWindow {
+        app_id: app_id.filter(|a| !a.is_empty()).map(str::to_string),
+        pid: node["pid"].as_u64().and_then(|p| u32::try_from(p).ok()),
+    };
+    // a focused workspace or output is not a window
+    (window != FocusedWindow::default()).then_some(window)
+}
+
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let out = Command::new(program).args(args).output().ok()?;
+    if !out.status.success() {
+        log::debug!("focus: {} {} failed", program, args.join(" "));
+        return None;
+    }
+    Some(String::from_utf8_lossy(&out.stdout).into_owned())
+}
+
+/// Asks the running compositor for the focused window. Hyprland and sway
+/// are supported; elsewhere, or with nothing focused, there is none.
+pub fn focused_window() -> Option<FocusedWindow> {
+    if env::var_os("HYPRLAND_INSTANCE_SIGNATURE").is_some() {
+        return parse_hyprland(&command_output("hyprctl", &["activewindow", "-j"])?);
+    }
+    if env::var_os("SWAYSOCK").is_some() {
+        return parse_sway(&command_output("swaymsg", &["-t", "get_tree"])?);
+    }
+    log::debug!("focus: no supported compositor found");
+    None
+}
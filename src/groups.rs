@@ -0,0 +1,75 @@
+use crate::audio::Stream;
+
+/// Identifies the app a stream belongs to: its Flatpak id if it has one,
+/// otherwise its name (ignoring case) together with its binary, since
+/// Electron apps tend to share a name.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AppIdentity {
+    Flatpak(String),
+    Process { name: String, binary: Option<String> },
+}
+
+impl AppIdentity {
+    pub fn of(s: &Stream) -> Self {
+        match &s.app_id {
+            Some(id) => AppIdentity::Flatpak(id.clone()),
+            None => AppIdentity::Process { name: s.name.to_lowercase(), binary: s.binary.clone() },
+        }
+    }
+}
+
+/// The streams of one app, shown as a single control.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamGroup {
+    pub identity: AppIdentity,
+    /// Never empty.
+    pub streams: Vec<Stream>,
+}
+
+/// Groups streams by app, in order of each app's first stream.
+pub fn group_streams(streams: Vec<Stream>) -> Vec<StreamGroup> {
+    let mut groups: Vec<StreamGroup> = Vec::new();
+    for s in streams {
+        let identity = AppIdentity::of(&s);
+        match groups.iter_mut().find(|g| g.identity == identity) {
+            Some(g) => g.streams.push(s),
+            None => groups.push(StreamGroup { identity, streams: vec![s] }),
+        }
+    }
+    groups
+}
+
+impl StreamGroup {
+    /// The stream standing for the whole group: its name, icon and
+    /// config entries.
+    pub fn first(&self) -> &Stream {
+        &self.streams[0]
+    }
+
+    pub fn ids(&self) -> Vec<u32> {
+        self.streams.iter().map(|s| s.id).collect()
+    }
+
+    /// The master level: the loudest member.
+    pub fn volume_01(&self) -> f32 {
+        self.streams.iter().map(|s| s.volume_01).fold(0.0, f32::max)
+    }
+
+    /// Muted only when every member is.
+    pub fn mute(&self) -> bool {
+        self.streams.iter().all(|s| s.mute)
+    }
+
+    /// Volumes that bring the master level to `master_01` while keeping
+    /// the members' proportions. A silent group is set level.
+    pub fn scaled(&self, master_01: f32) -> Vec<(u32, f32)> {
+        let loudest = self.volume_01();
+        self.streams
+            .iter()
+            .map(|s| {
+                let ratio = if loudest > 0.0 { s.volume_01 / loudest } else { 1.0 };
+                (s.id, (ratio * master_01).clamp(0.0, 1.0))
+            })
+            .collect()
+    }
+}
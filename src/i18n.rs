@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+use thiserror::Error;
+
+use crate::desktop::user_locale_keys;
+
+/// Translations shipped with the program, by locale key. To add a
+/// language, copy `po/de.po`, translate it and list it here.
+const CATALOGS: &[(&str, &str)] = &[("de", include_str!("../po/de.po"))];
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CatalogError {
+    #[error("line {0}: {1}")]
+    Syntax(usize, String),
+    #[error("bad Plural-Forms {0:?}")]
+    PluralForms(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    // Binding strength of the binary operators, as in C.
+    fn precedence(self) -> u8 {
+        match self {
+            Op::Or => 1,
+            Op::And => 2,
+            Op::Eq | Op::Ne => 3,
+            Op::Lt | Op::Le | Op::Gt | Op::Ge => 4,
+            Op::Add | Op::Sub => 5,
+            Op::Mul | Op::Div | Op::Rem => 6,
+        }
+    }
+
+    fn apply(self, a: u64, b: u64) -> Option<u64> {
+        Some(match self {
+            Op::Or => (a != 0 || b != 0) as u64,
+            Op::And => (a != 0 && b != 0) as u64,
+            Op::Eq => (a == b) as u64,
+            Op::Ne => (a != b) as u64,
+            Op::Lt => (a < b) as u64,
+            Op::Le => (a <= b) as u64,
+            Op::Gt => (a > b) as u64,
+            Op::Ge => (a >= b) as u64,
+            Op::Add => a.wrapping_add(b),
+            Op::Sub => a.wrapping_sub(b),
+            Op::Mul => a.wrapping_mul(b),
+            Op::Div => a.checked_div(b)?,
+            Op::Rem => a.checked_rem(b)?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    N,
+    Num(u64),
+    Not(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, n: u64) -> Option<u64> {
+        match self {
+            Expr::N => Some(n),
+            Expr::Num(v) => Some(*v),
+            Expr::Not(e) => Some((e.eval(n)? == 0) as u64),
+            Expr::Binary(op, a, b) => op.apply(a.eval(n)?, b.eval(n)?),
+            Expr::If(cond, then, other) => if cond.eval(n)? != 0 { then.eval(n) } else { other.eval(n) },
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    N,
+    Num(u64),
+    Op(Op),
+    Not,
+    Question,
+    Colon,
+    Open,
+    Close,
+}
+
+fn tokenize(s: &str) -> Option<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        let mut next_is = |want: char| chars.next_if_eq(&want).is_some();
+        let token = match c {
+            c if c.is_whitespace() => continue,
+            'n' => Token::N,
+            '0'..='9' => {
+                let mut v = c.to_digit(10)? as u64;
+                while let Some(d) = chars.next_if(char::is_ascii_digit) {
+                    v = v.checked_mul(10)?.checked_add(d.to_digit(10)? as u64)?;
+                }
+                Token::Num(v)
+            }
+            '|' if next_is('|') => Token::Op(Op::Or),
+            '&' if next_is('&') => Token::Op(Op::And),
+            '=' if next_is('=') => Token::Op(Op::Eq),
+            '!' if next_is('=') => Token::Op(Op::Ne),
+            '!' => Token::Not,
+            '<' if next_is('=') => Token::Op(Op::Le),
+            '<' => Token::Op(Op::Lt),
+            '>' if next_is('=') => Token::Op(Op::Ge),
+            '>' => Token::Op(Op::Gt),
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '%' => Token::Op(Op::Rem),
+            '?' => Token::Question,
+            ':' => Token::Colon,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            _ => return None,
+        };
+        tokens.push(token);
+    }
+    Some(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, want: &Token) -> bool {
+        let found = self.tokens.get(self.pos) == Some(want);
+        if found {
+            self.pos += 1;
+        }
+        found
+    }
+
+    // cond ? a : b, right-associative, below every binary operator
+    fn ternary(&mut self) -> Option<Expr> {
+        let cond = self.binary(1)?;
+        if !self.eat(&Token::Question) {
+            return Some(cond);
+        }
+        let then = self.ternary()?;
+        if !self.eat(&Token::Colon) {
+            return None;
+        }
+        Some(Expr::If(Box::new(cond), Box::new(then), Box::new(self.ternary()?)))
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Option<Expr> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op)) = self.tokens.get(self.pos).cloned() {
+            if op.precedence() < min_precedence {
+                break;
+            }
+            self.pos += 1;
+            let right = self.binary(op.precedence() + 1)?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+        Some(left)
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        match self.next()? {
+            Token::N => Some(Expr::N),
+            Token::Num(v) => Some(Expr::Num(v)),
+            Token::Not => Some(Expr::Not(Box::new(self.unary()?))),
+            Token::Open => {
+                let inner = self.ternary()?;
+                self.eat(&Token::Close).then_some(inner)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Which plural form to use for a count, from a catalog's
+/// `Plural-Forms: nplurals=2; plural=(n != 1);` header.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PluralRule {
+    nplurals: usize,
+    expr: Expr,
+}
+
+impl Default for PluralRule {
+    /// English: one form for 1, another for everything else.
+    fn default() -> Self {
+        PluralRule { nplurals: 2, expr: Expr::Binary(Op::Ne, Box::new(Expr::N), Box::new(Expr::Num(1))) }
+    }
+}
+
+impl PluralRule {
+    pub fn parse(header: &str) -> Result<PluralRule, CatalogError> {
+        let bad = || CatalogError::PluralForms(header.to_string());
+        let mut nplurals = None;
+        let mut plural = None;
+        for part in header.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+            match part.split_once('=').map(|(k, v)| (k.trim(), v.trim())) {
+                Some(("nplurals", v)) => nplurals = v.parse::<usize>().ok(),
+                Some(("plural", v)) => plural = Some(v),
+                _ => return Err(bad()),
+            }
+        }
+        let nplurals = nplurals.filter(|&n| n > 0).ok_or_else(bad)?;
+        let mut parser = Parser { tokens: tokenize(plural.ok_or_else(bad)?).ok_or_else(bad)?, pos: 0 };
+        let expr = parser.ternary().filter(|_| parser.pos == parser.tokens.len()).ok_or_else(bad)?;
+        Ok(PluralRule { nplurals, expr })
+    }
+
+    pub fn nplurals(&self) -> usize {
+        self.nplurals
+    }
+
+    /// The form for `n`. An expression that divides by zero or picks a
+    /// form past `nplurals` gives the first form.
+    pub fn index(&self, n: u64) -> usize {
+        self.expr.eval(n).map(|i| i as usize).filter(|&i| i < self.nplurals).unwrap_or(0)
+    }
+}
+
+// The text of a "quoted" po string, with C escapes resolved.
+fn unquote(s: &str) -> Option<String> {
+    let inner = s.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        out.push(match chars.next()? {
+            'n' => '\n',
+            't' => '\t',
+            'r' => '\r',
+            c @ ('"' | '\\') => c,
+            _ => return None,
+        });
+    }
+    Some(out)
+}
+
+#[derive(Debug, Default)]
+struct PoEntry {
+    context: Option<String>,
+    id: Option<String>,
+    plural_id: Option<String>,
+    translations: Vec<String>,
+    fuzzy: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Field {
+    Context,
+    Id,
+    PluralId,
+    Translation(usize),
+}
+
+/// Translated messages from a gettext `.po` file. Fuzzy and untranslated
+/// entries are left out, so they show in English.
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    plural: PluralRule,
+    /// By msgid, or `msgctxt\u{4}msgid`: the translation, or each plural
+    /// form of it.
+    messages: HashMap<String, Vec<String>>,
+}
+
+impl Catalog {
+    pub fn parse(text: &str) -> Result<Catalog, CatalogError> {
+        let mut catalog = Catalog::default();
+        let mut entry = PoEntry::default();
+        let mut field = None;
+        for (i, line) in text.lines().enumerate() {
+            let syntax = |msg: &str| CatalogError::Syntax(i + 1, msg.to_string());
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                // comments belong to the entry after them
+                if !entry.translations.is_empty() {
+                    catalog.add(std::mem::take(&mut entry))?;
+                    field = None;
+                }
+                if line.starts_with("#,") && line.contains("fuzzy") {
+                    entry.fuzzy = true;
+                }
+                continue;
+            }
+            if line.starts_with('"') {
+                let more = unquote(line).ok_or_else(|| syntax("bad string"))?;
+                let target = match field.ok_or_else(|| syntax("string outside an entry"))? {
+                    Field::Context => entry.context.as_mut(),
+                    Field::Id => entry.id.as_mut(),
+                    Field::PluralId => entry.plural_id.as_mut(),
+                    Field::Translation(n) => entry.translations.get_mut(n),
+                };
+                target.ok_or_else(|| syntax("string outside an entry"))?.push_str(&more);
+                continue;
+            }
+
+            let (keyword, rest) = line.split_once(char::is_whitespace).ok_or_else(|| syntax("expected a keyword"))?;
+            let value = unquote(rest).ok_or_else(|| syntax("bad string"))?;
+            // a msgctxt or msgid after a translation starts the next entry
+            if matches!(keyword, "msgctxt" | "msgid") && !entry.translations.is_empty() {
+                catalog.add(std::mem::take(&mut entry))?;
+            }
+            field = Some(match keyword {
+                "msgctxt" => {
+                    entry.context = Some(value);
+                    Field::Context
+                }
+                "msgid" => {
+                    entry.id = Some(value);
+                    Field::Id
+                }
+                "msgid_plural" => {
+                    entry.plural_id = Some(value);
+                    Field::PluralId
+                }
+                "msgstr" => {
+                    entry.translations = vec![value];
+                    Field::Translation(0)
+                }
+                _ => {
+                    let n = keyword
+                        .strip_prefix("msgstr[")
+                        .and_then(|k| k.strip_suffix(']'))
+                        .and_then(|n| n.parse::<usize>().ok())
+                        .filter(|&n| n == entry.translations.len())
+                        .ok_or_else(|| syntax(&format!("unexpected {}", keyword)))?;
+                    entry.translations.push(value);
+                    Field::Translation(n)
+                }
+            });
+        }
+        if entry.id.is_some() {
+            catalog.add(entry)?;
+        }
+        Ok(catalog)
+    }
+
+    fn add(&mut self, entry: PoEntry) -> Result<(), CatalogError> {
+        let Some(id) = entry.id else { return Ok(()) };
+        if id.is_empty() && entry.context.is_none() {
+            // the header
+            let plural_forms = entry
+                .translations
+                .first()
+                .and_then(|header| header.lines().find_map(|l| l.strip_prefix("Plural-Forms:")));
+            if let Some(forms) = plural_forms {
+                self.plural = PluralRule::parse(forms)?;
+            }
+            return Ok(());
+        }
+        if entry.fuzzy || entry.translations.iter().any(String::is_empty) {
+            return Ok(());
+        }
+        let key = match entry.context {
+            Some(context) => format!("{}\u{4}{}", context, id),
+            None => id,
+        };
+        self.messages.insert(key, entry.translations);
+        Ok(())
+    }
+
+    pub fn plural_rule(&self) -> &PluralRule {
+        &self.plural
+    }
+
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    pub fn contains(&self, msgid: &str) -> bool {
+        self.messages.contains_key(msgid)
+    }
+
+    pub fn gettext<'a>(&'a self, msgid: &'a str) -> &'a str {
+        self.messages.get(msgid).and_then(|t| t.first()).map_or(msgid, String::as_str)
+    }
+
+    /// The form of a message for `n` things.
+    pub fn ngettext<'a>(&'a self, singular: &'a str, plural: &'a str, n: u64) -> &'a str {
+        match self.messages.get(singular).and_then(|t| t.get(self.plural.index(n))) {
+            Some(translated) => translated,
+            None if n == 1 => singular,
+            None => plural,
+        }
+    }
+}
+
+/// The shipped catalog for the first of `locale_keys` that has one.
+pub fn catalog_for(locale_keys: &[String]) -> Option<Catalog> {
+    let (locale, text) = locale_keys
+        .iter()
+        .find_map(|key| CATALOGS.iter().find(|(locale, _)| locale == key))?;
+    match Catalog::parse(text) {
+        Ok(catalog) => Some(catalog),
+        Err(e) => {
+            log::warn!("i18n: catalog {} is broken: {}", locale, e);
+            None
+        }
+    }
+}
+
+fn catalog() -> Option<&'static Catalog> {
+    static CATALOG: OnceLock<Option<Catalog>> = OnceLock::new();
+    CATALOG.get_or_init(|| catalog_for(&user_locale_keys())).as_ref()
+}
+
+/// `msgid` in the user's language.
+pub fn gettext(msgid: &str) -> &str {
+    match catalog() {
+        Some(catalog) => catalog.gettext(msgid),
+        None => msgid,
+    }
+}
+
+/// The `singular` or `plural` message for `n` things, in the user's
+/// language, which may have more forms than two.
+pub fn ngettext<'a>(singular: &'a str, plural: &'a str, n: u64) -> &'a str {
+    match catalog() {
+        Some(catalog) => catalog.ngettext(singular, plural, n),
+        None if n == 1 => singular,
+        None => plural,
+    }
+}
+
+/// Fills the `{}` in a translated message with `args` in turn; `{0}`,
+/// `{1}`… name one, so a translation can reorder them. `{{` and `}}` are
+/// literal braces.
+pub fn format(template: &str, args: &[&dyn Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut next = 0;
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        let tail = &rest[i..];
+        if let Some(after) = tail.strip_prefix("{{").or_else(|| tail.strip_prefix("}}")) {
+            out.push_str(&tail[..1]);
+            rest = after;
+            continue;
+        }
+        let placeholder = tail.strip_prefix('{').and_then(|t| t.split_once('}'));
+        let Some((index, after)) = placeholder.filter(|(index, _)| index.chars().all(|c| c.is_ascii_digit())) else {
+            out.push_str(&tail[..1]);
+            rest = &tail[1..];
+            continue;
+        };
+        let n = if index.is_empty() {
+            next += 1;
+            next - 1
+        } else {
+            index.parse().unwrap_or(usize::MAX)
+        };
+        match args.get(n) {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str(&tail[..tail.len() - after.len()]),
+        }
+        rest = after;
+    }
+    out.push_str(rest);
+    out
+}
+
+/// A message in the user's language: `tr!("Mute")`, or with arguments
+/// for its `{}` placeholders, `tr!("Failed to load scene {}", name)`.
+#[macro_export]
+macro_rules! tr {
+    ($msgid:expr) => {
+        $crate::i18n::gettext($msgid).to_string()
+    };
+    ($msgid:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format($crate::i18n::gettext($msgid), &[$(&$arg),+])
+    };
+}
+
+/// A message about `n` things in the user's language, with its
+/// placeholders filled by the arguments that follow `n`:
+/// `trn!("{} stream", "{} streams", n, n)`.
+#[macro_export]
+macro_rules! trn {
+    ($singular:expr, $plural:expr, $n:expr, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::format($crate::i18n::ngettext($singular, $plural, $n as u64), &[$(&$arg),+])
+    };
+}
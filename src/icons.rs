@@ -0,0 +1,228 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use ini::Ini;
+
+use crate::xdg;
+
+const EXTENSIONS: [&str; 3] = ["png", "svg", "xpm"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SizeKind {
+    Fixed,
+    Scalable,
+    Threshold,
+}
+
+/// One subdirectory listed in a theme's `index.theme`.
+#[derive(Debug, Clone)]
+struct IconDir {
+    path: String,
+    size: i32,
+    scale: i32,
+    kind: SizeKind,
+    min_size: i32,
+    max_size: i32,
+    threshold: i32,
+}
+
+impl IconDir {
+    fn parse(path: &str, props: &ini::Properties) -> Option<IconDir> {
+        let int = |key: &str| props.get(key).and_then(|v| v.trim().parse::<i32>().ok());
+        let size = int("Size")?;
+        let kind = match props.get("Type").map(str::trim) {
+            Some("Fixed") => SizeKind::Fixed,
+            Some("Scalable") => SizeKind::Scalable,
+            _ => SizeKind::Threshold,
+        };
+        Some(IconDir {
+            path: path.to_string(),
+            size,
+            scale: int("Scale").unwrap_or(1),
+            kind,
+            min_size: int("MinSize").unwrap_or(size),
+            max_size: int("MaxSize").unwrap_or(size),
+            threshold: int("Threshold").unwrap_or(2),
+        })
+    }
+
+    fn matches_size(&self, size: i32, scale: i32) -> bool {
+        if self.scale != scale {
+            return false;
+        }
+        match self.kind {
+            SizeKind::Fixed => self.size == size,
+            SizeKind::Scalable => (self.min_size..=self.max_size).contains(&size),
+            SizeKind::Threshold => (self.size - self.threshold..=self.size + self.threshold).contains(&size),
+        }
+    }
+
+    fn size_distance(&self, size: i32, scale: i32) -> i32 {
+        let wanted = size * scale;
+        let (min, max) = match self.kind {
+            SizeKind::Fixed => (self.size, self.size),
+            SizeKind::Scalable => (self.min_size, self.max_size),
+            SizeKind::Threshold => (self.size - self.threshold, self.size + self.threshold),
+        };
+        if wanted < min * self.scale {
+            min * self.scale - wanted
+        } else if wanted > max * self.scale {
+            wanted - max * self.scale
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ThemeIndex {
+    name: String,
+    dirs: Vec<IconDir>,
+    parents: Vec<String>,
+}
+
+impl ThemeIndex {
+    fn parse(name: &str, text: &str) -> Option<ThemeIndex> {
+        let conf = Ini::load_from_str(text).ok()?;
+        let main = conf.section(Some("Icon Theme"))?;
+        let list = |key: &str| -> Vec<String> {
+            main.get(key)
+                .map(|v| v.split(',').map(str::trim).filter(|d| !d.is_empty()).map(String::from).collect())
+                .unwrap_or_default()
+        };
+        let dirs = list("Directories")
+            .into_iter()
+            .chain(list("ScaledDirectories"))
+            .filter_map(|dir| IconDir::parse(&dir, conf.section(Some(dir.as_str()))?))
+            .collect();
+        Some(ThemeIndex { name: name.to_string(), dirs, parents: list("Inherits") })
+    }
+}
+
+/// Finds icon files the way the freedesktop Icon Theme spec describes:
+/// the theme, then its parents depth first, then `hicolor`, then plain
+/// files in the base directories (`/usr/share/pixmaps` and the like).
+#[derive(Debug, Clone)]
+pub struct IconResolver {
+    base_dirs: Vec<PathBuf>,
+    /// The lookup order of themes; each appears once.
+    themes: Vec<ThemeIndex>,
+}
+
+impl IconResolver {
+    /// `~/.icons`, `$XDG_DATA_DIRS/icons` and `/usr/share/pixmaps`.
+    pub fn default_base_dirs() -> Vec<PathBuf> {
+        let mut dirs = vec![PathBuf::from(shellexpand::tilde("~/.icons").to_string())];
+        dirs.extend(xdg::data_dirs().into_iter().map(|d| d.join("icons")));
+        dirs.push(PathBuf::from("/usr/share/pixmaps"));
+        dirs
+    }
+
+    pub fn new(theme: &str) -> Self {
+        IconResolver::with_base_dirs(theme, IconResolver::default_base_dirs())
+    }
+
+    /// Resolves against the given base directories, most important first.
+    pub fn with_base_dirs(theme: &str, base_dirs: Vec<PathBuf>) -> Self {
+        let mut resolver = IconResolver { base_dirs, themes: Vec::new() };
+        let mut seen = HashSet::new();
+        resolver.add_theme(theme, &mut seen);
+        resolver.add_theme("hicolor", &mut seen);
+        resolver
+    }
+
+    fn add_theme(&mut self, name: &str, seen: &mut HashSet<String>) {
+        if !seen.insert(name.to_string()) {
+            return;
+        }
+        let Some(index) = self.load_index(name) else {
+            log::debug!("icons: no index.theme for {:?}", name);
+            return;
+        };
+        let parents = index.parents.clone();
+        self.themes.push(index);
+        for parent in parents {
+            self.add_theme(&parent, seen);
+        }
+    }
+
+    // The first base directory with the theme's index.theme defines it.
+    fn load_index(&self, name: &str) -> Option<ThemeIndex> {
+        self.base_dirs.iter().find_map(|base| {
+            let text = std::fs::read_to_string(base.join(name).join("index.theme")).ok()?;
+            ThemeIndex::parse(name, &text)
+        })
+    }
+
+    /// Names of the themes searched, in order.
+    pub fn theme_chain(&self) -> Vec<&str> {
+        self.themes.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    /// Directories whose modification time changes when icons are added
+    /// or removed: the base directories and each theme's directory in
+    /// them, which `gtk-update-icon-cache` rewrites on every install.
+    pub fn watched_dirs(&self) -> Vec<PathBuf> {
+        let themes = self.themes.iter().flat_map(|t| self.base_dirs.iter().map(move |b| b.join(&t.name)));
+        self.base_dirs.iter().cloned().chain(themes).collect()
+    }
+
+    /// The file for an icon name at `size` logical pixels and `scale`. An
+    /// absolute path is returned as is when it exists; a stray extension
+    /// (`Icon=foo.png`, common in desktop files) is ignored.
+    pub fn lookup(&self, icon: &str, size: i32, scale: i32) -> Option<PathBuf> {
+        let path = Path::new(icon);
+        if path.is_absolute() {
+            return path.is_file().then(|| path.to_path_buf());
+        }
+        let name = EXTENSIONS
+            .iter()
+            .find_map(|ext| icon.strip_suffix(&format!(".{}", ext)))
+            .unwrap_or(icon);
+        if name.is_empty() || name.contains('/') {
+            return None;
+        }
+
+        self.themes
+            .iter()
+            .find_map(|theme| self.lookup_in(theme, name, size, scale))
+            .or_else(|| self.lookup_fallback(name))
+    }
+
+    fn candidates<'a>(&'a self, theme: &'a ThemeIndex, dir: &'a IconDir, name: &'a str) -> impl Iterator<Item = PathBuf> + 'a {
+        self.base_dirs.iter().flat_map(move |base| {
+            let dir = base.join(&theme.name).join(&dir.path);
+            EXTENSIONS.iter().map(move |ext| dir.join(format!("{}.{}", name, ext)))
+        })
+    }
+
+    fn lookup_in(&self, theme: &ThemeIndex, name: &str, size: i32, scale: i32) -> Option<PathBuf> {
+        let exact = theme
+            .dirs
+            .iter()
+            .filter(|d| d.matches_size(size, scale))
+            .find_map(|d| self.candidates(theme, d, name).find(|p| p.is_file()));
+        if exact.is_some() {
+            return exact;
+        }
+
+        let mut closest: Option<(i32, PathBuf)> = None;
+        for dir in &theme.dirs {
+            let distance = dir.size_distance(size, scale);
+            if closest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                continue;
+            }
+            if let Some(file) = self.candidates(theme, dir, name).find(|p| p.is_file()) {
+                closest = Some((distance, file));
+            }
+        }
+        closest.map(|(_, file)| file)
+    }
+
+    fn lookup_fallback(&self, name: &str) -> Option<PathBuf> {
+        self.base_dirs
+            .iter()
+            .flat_map(|base| EXTENSIONS.iter().map(move |ext| base.join(format!("{}.{}", name, ext))))
+            .find(|p| p.is_file())
+    }
+}
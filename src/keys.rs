@@ -0,0 +1,155 @@
+/// What a key does in the mixer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    /// Selects the column to the left.
+    Previous,
+    Next,
+    VolumeUp,
+    VolumeDown,
+    /// A larger step up, see `page_step` in the config.
+    PageUp,
+    PageDown,
+    ToggleMute,
+    /// Sets the volume to this many tenths.
+    Jump(u8),
+    /// Closes the popup; the window ignores it.
+    Close,
+}
+
+/// The names of the `[keys]` config settings, one per action.
+pub const ACTION_NAMES: &[&str] = &[
+    "previous",
+    "next",
+    "volume_up",
+    "volume_down",
+    "page_up",
+    "page_down",
+    "mute",
+    "close",
+    "jump_0",
+    "jump_1",
+    "jump_2",
+    "jump_3",
+    "jump_4",
+    "jump_5",
+    "jump_6",
+    "jump_7",
+    "jump_8",
+    "jump_9",
+];
+
+impl Action {
+    pub fn from_name(name: &str) -> Option<Action> {
+        Some(match name {
+            "previous" => Action::Previous,
+            "next" => Action::Next,
+            "volume_up" => Action::VolumeUp,
+            "volume_down" => Action::VolumeDown,
+            "page_up" => Action::PageUp,
+            "page_down" => Action::PageDown,
+            "mute" => Action::ToggleMute,
+            "close" => Action::Close,
+            _ => {
+                let tenths = name.strip_prefix("jump_")?.parse::<u8>().ok()?;
+                return (tenths <= 9).then_some(Action::Jump(tenths));
+            }
+        })
+    }
+}
+
+/// A key with the modifiers held along: `Up`, `Ctrl+m`. Keys go by their
+/// GDK names, so `Page_Up`, `KP_5` or `space`; letters are as typed, `M`
+/// being what Shift+m gives.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chord {
+    key: String,
+    ctrl: bool,
+    alt: bool,
+}
+
+impl Chord {
+    pub fn parse(text: &str) -> Result<Chord, String> {
+        let mut parts: Vec<&str> = text.split('+').map(str::trim).collect();
+        let key = parts.pop().unwrap_or_default();
+        if key.is_empty() || !key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(format!("expected a key name such as Up, Page_Up or m, got {:?}", text.trim()));
+        }
+        let mut chord = Chord { key: key.to_string(), ctrl: false, alt: false };
+        for modifier in parts {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                _ => return Err(format!("expected Ctrl or Alt before the key, got {:?}", modifier)),
+            }
+        }
+        Ok(chord)
+    }
+
+    fn matches(&self, key: &str, ctrl: bool, alt: bool) -> bool {
+        self.key == key && self.ctrl == ctrl && self.alt == alt
+    }
+}
+
+/// Parses a `,`-separated list of chords; an empty list unbinds.
+pub fn parse_chords(value: &str) -> Result<Vec<Chord>, String> {
+    value.split(',').map(str::trim).filter(|c| !c.is_empty()).map(Chord::parse).collect()
+}
+
+/// The keys bound to each action, from the `[keys]` config section:
+///
+/// ```ini
+/// [keys]
+/// volume_up = Up, k
+/// mute = m, Ctrl+m
+/// close =
+/// ```
+///
+/// Actions not set keep their default keys: arrows, `Page_Up` and
+/// `Page_Down`, `m`, `Escape`, and `0`–`9` on either row for `jump_0`
+/// to `jump_9`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyBindings {
+    bindings: Vec<(Action, Vec<Chord>)>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        let keys = |names: &[&str]| names.iter().map(|n| Chord::parse(n).expect("default keys are valid")).collect();
+        let mut bindings = vec![
+            (Action::Previous, keys(&["Left"])),
+            (Action::Next, keys(&["Right"])),
+            (Action::VolumeUp, keys(&["Up"])),
+            (Action::VolumeDown, keys(&["Down"])),
+            (Action::PageUp, keys(&["Page_Up"])),
+            (Action::PageDown, keys(&["Page_Down"])),
+            (Action::ToggleMute, keys(&["m"])),
+            (Action::Close, keys(&["Escape"])),
+        ];
+        for tenths in 0..=9 {
+            bindings.push((Action::Jump(tenths), keys(&[&tenths.to_string(), &format!("KP_{}", tenths)])));
+        }
+        KeyBindings { bindings }
+    }
+}
+
+impl KeyBindings {
+    /// Replaces the keys of `action`.
+    pub fn bind(&mut self, action: Action, chords: Vec<Chord>) {
+        match self.bindings.iter_mut().find(|(a, _)| *a == action) {
+            Some((_, bound)) => *bound = chords,
+            None => self.bindings.push((action, chords)),
+        }
+    }
+
+    pub fn keys_for(&self, action: Action) -> &[Chord] {
+        self.bindings.iter().find(|(a, _)| *a == action).map_or(&[], |(_, chords)| chords)
+    }
+
+    /// The action of a key pressed, given by its GDK name.
+    pub fn action_for(&self, key: &str, ctrl: bool, alt: bool) -> Option<Action> {
+        self.bindings
+            .iter()
+            .find(|(_, chords)| chords.iter().any(|c| c.matches(key, ctrl, alt)))
+            .map(|(action, _)| *action)
+    }
+}
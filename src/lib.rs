@@ -1,4 +1,25 @@
 // src/lib.rs
 pub mod audio;
+pub mod bar;
+pub mod cache;
+pub mod cli;
+pub mod config;
+pub mod desktop;
+pub mod focus;
+pub mod groups;
+pub mod i18n;
+pub mod icons;
+pub mod keys;
+pub mod limits;
+pub mod lock;
+pub mod memory;
+pub mod osd;
 pub mod pipewire_cli;
 pub mod pulseaudio_cli;
+pub mod rules;
+pub mod scenes;
+pub mod selector;
+pub mod values;
+pub mod worker;
+pub mod writes;
+pub mod xdg;
@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use ini::Ini;
+use thiserror::Error;
+
+use crate::audio::{AudioBackend, AudioError, Device, Stream, Subscription};
+use crate::tr;
+use crate::values::parse_percent;
+use crate::xdg;
+
+// Volumes read back from the server are rounded to whole percents.
+const EPSILON: f32 = 0.005;
+
+#[derive(Error, Debug)]
+pub enum LimitsError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("limits: {0}")]
+    Parse(String),
+}
+
+/// Maximum volumes, global and per application:
+///
+/// ```ini
+/// [limits]
+/// global = 90%
+///
+/// [apps]
+/// firefox = 60%
+/// steam = 50%
+/// ```
+///
+/// App keys match the stream's application name or binary, ignoring case.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Limits {
+    pub global: Option<f32>,
+    apps: HashMap<String, f32>,
+}
+
+impl Limits {
+    /// `$XDG_CONFIG_HOME/wlvolctl/limits`
+    pub fn default_path() -> PathBuf {
+        xdg::config_dir().join("limits")
+    }
+
+    /// Loads a limits file; a missing file means no limits.
+    pub fn load(path: &Path) -> Result<Limits, LimitsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Limits::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Limits::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Loads the default file, reporting errors and falling back to no limits.
+    pub fn load_default() -> Limits {
+        let path = Limits::default_path();
+        Limits::load(&path).unwrap_or_else(|e| {
+            eprintln!("{}", tr!("Ignoring limits in {}: {}", path.display(), e));
+            Limits::default()
+        })
+    }
+
+    pub fn parse(text: &str) -> Result<Limits, LimitsError> {
+        let conf = Ini::load_from_str(text).map_err(|e| LimitsError::Parse(e.to_string()))?;
+        let percent = |key: &str, v: &str| {
+            parse_percent(v).ok_or_else(|| LimitsError::Parse(format!("{}: bad volume {:?}", key, v)))
+        };
+
+        let mut limits = Limits::default();
+        for (section, props) in conf.iter() {
+            match section {
+                None if props.is_empty() => {}
+                Some("limits") => {
+                    for (key, value) in props.iter() {
+                        match key {
+                            "global" => limits.global = Some(percent(key, value)?),
+                            _ => return Err(LimitsError::Parse(format!("[limits] unknown key {:?}", key))),
+                        }
+                    }
+                }
+                Some("apps") => {
+                    for (app, value) in props.iter() {
+                        limits.apps.insert(app.to_lowercase(), percent(app, value)?);
+                    }
+                }
+                Some(other) => return Err(LimitsError::Parse(format!("unknown section [{}]", other))),
+                None => return Err(LimitsError::Parse("keys outside of [limits] or [apps]".into())),
+            }
+        }
+        Ok(limits)
+    }
+
+    /// The strictest limit that applies to `s`, if any.
+    pub fn cap_for(&self, s: &Stream) -> Option<f32> {
+        let app = self
+            .apps
+            .get(&s.name.to_lowercase())
+            .or_else(|| s.binary.as_ref().and_then(|b| self.apps.get(&b.to_lowercase())))
+            .copied();
+        match (self.global, app) {
+            (Some(g), Some(a)) => Some(g.min(a)),
+            (g, a) => g.or(a),
+        }
+    }
+}
+
+/// Backend wrapper that enforces [`Limits`]: requests above a stream's cap
+/// are clamped, and streams found above their cap when listing (the app
+/// raised itself) are pulled back down. The limits can be swapped while
+/// the backend is in use, for a reloaded file.
+pub struct CappedBackend<B> {
+    inner: B,
+    limits: Mutex<Limits>,
+    // last listed state, to find the cap for a stream id
+    known: Mutex<HashMap<u32, Stream>>,
+}
+
+impl<B: AudioBackend> CappedBackend<B> {
+    pub fn new(inner: B, limits: Limits) -> Self {
+        CappedBackend { inner, limits: Mutex::new(limits), known: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    pub fn set_limits(&self, limits: Limits) {
+        *self.limits.lock().unwrap() = limits;
+    }
+
+    fn cap_for_id(&self, stream_id: u32) -> Result<Option<f32>, AudioError> {
+        if !self.known.lock().unwrap().contains_key(&stream_id) {
+            self.list_streams()?;
+        }
+        let known = self.known.lock().unwrap();
+        let limits = self.limits.lock().unwrap();
+        Ok(known.get(&stream_id).and_then(|s| limits.cap_for(s)))
+    }
+}
+
+impl<B: AudioBackend> AudioBackend for CappedBackend<B> {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        let mut streams = self.inner.list_streams()?;
+        let limits = self.limits.lock().unwrap().clone();
+
+        for s in streams.iter_mut() {
+            if let Some(cap) = limits.cap_for(s)
+                && s.volume_01 > cap + EPSILON
+            {
+                log::info!("limits: {} (#{}) at {:.2}, pulling down to {:.2}", s.name, s.id, s.volume_01, cap);
+                // the stream may be gone since it was listed; the others
+                // are still worth showing
+                match self.inner.set_volume(s.id, cap) {
+                    Ok(()) => s.volume_01 = cap,
+                    Err(e) => log::warn!("limits: cannot pull down {} (#{}): {}", s.name, s.id, e),
+                }
+            }
+        }
+
+        *self.known.lock().unwrap() = streams.iter().map(|s| (s.id, s.clone())).collect();
+        Ok(streams)
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        let vol = match self.cap_for_id(stream_id)? {
+            Some(cap) => vol_01.min(cap),
+            None => vol_01,
+        };
+        self.inner.set_volume(stream_id, vol)
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.inner.set_mute(stream_id, mute)
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        self.inner.list_devices()
+    }
+
+    fn set_device_volume(&self, device_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.inner.set_device_volume(device_id, vol_01)
+    }
+
+    fn set_device_mute(&self, device_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.inner.set_device_mute(device_id, mute)
+    }
+
+    fn move_stream(&self, stream_id: u32, device_id: u32) -> Result<(), AudioError> {
+        self.inner.move_stream(stream_id, device_id)
+    }
+
+    fn subscribe(&self) -> Result<Subscription, AudioError> {
+        self.inner.subscribe()
+    }
+}
@@ -0,0 +1,196 @@
+use std::collections::{HashMap, VecDeque};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::audio::{AudioBackend, AudioError, Device, Stream, Subscription};
+use crate::xdg;
+
+// Volumes read back from the server are rounded to whole percents.
+const EPSILON: f32 = 0.005;
+
+/// How many reverts are kept in memory; the log file has them all.
+pub const MAX_REVERTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Locked {
+    volume_01: f32,
+    mute: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Change {
+    Volume { from: f32, to: f32 },
+    Mute { from: bool, to: bool },
+}
+
+/// `$XDG_STATE_HOME/wlvolctl/lock.log`
+pub fn default_log_path() -> PathBuf {
+    xdg::state_dir().join("lock.log")
+}
+
+/// A change made behind wlvolctl's back and undone. `change.from` is the
+/// locked value, `change.to` what the application set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revert {
+    pub at: SystemTime,
+    pub stream_id: u32,
+    pub app: String,
+    pub change: Change,
+}
+
+impl std::fmt::Display for Revert {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let secs = self.at.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        match self.change {
+            Change::Volume { from, to } => {
+                write!(f, "{} {} (#{}) changed volume {:.2} -> {:.2}, reverted", secs, self.app, self.stream_id, from, to)
+            }
+            Change::Mute { from, to } => {
+                write!(f, "{} {} (#{}) changed mute {} -> {}, reverted", secs, self.app, self.stream_id, from, to)
+            }
+        }
+    }
+}
+
+/// Backend wrapper that holds locked streams at their volume and mute.
+///
+/// Changes made through this wrapper move the locked value along; changes
+/// found when listing that did not come from here are reverted and
+/// recorded in the log file, the latest [`MAX_REVERTS`] of them also in
+/// memory for [`LockingBackend::reverts_of`]. Locks live in the wrapper,
+/// so each UI instance has its own and they end with it.
+pub struct LockingBackend<B> {
+    inner: B,
+    locked: Mutex<HashMap<u32, Locked>>,
+    reverts: Mutex<VecDeque<Revert>>,
+    log_path: Option<PathBuf>,
+}
+
+impl<B: AudioBackend> LockingBackend<B> {
+    pub fn new(inner: B) -> Self {
+        LockingBackend { inner, locked: Mutex::new(HashMap::new()), reverts: Mutex::new(VecDeque::new()), log_path: None }
+    }
+
+    /// Also appends every revert to `path`.
+    pub fn with_log(inner: B, path: PathBuf) -> Self {
+        LockingBackend { log_path: Some(path), ..LockingBackend::new(inner) }
+    }
+
+    pub fn inner(&self) -> &B {
+        &self.inner
+    }
+
+    /// Locks `stream` at its current volume and mute, or releases it.
+    pub fn set_locked(&self, stream: &Stream, locked: bool) {
+        let mut map = self.locked.lock().unwrap();
+        if locked {
+            map.insert(stream.id, Locked { volume_01: stream.volume_01, mute: stream.mute });
+        } else {
+            map.remove(&stream.id);
+        }
+    }
+
+    pub fn is_locked(&self, stream_id: u32) -> bool {
+        self.locked.lock().unwrap().contains_key(&stream_id)
+    }
+
+    /// The recent reverts of these streams, oldest first.
+    pub fn reverts_of(&self, stream_ids: &[u32]) -> Vec<Revert> {
+        self.reverts.lock().unwrap().iter().filter(|r| stream_ids.contains(&r.stream_id)).cloned().collect()
+    }
+
+    fn record(&self, revert: Revert) {
+        log::info!("lock: {}", revert);
+        if let Some(path) = &self.log_path {
+            let written = path
+                .parent()
+                .map_or(Ok(()), fs::create_dir_all)
+                .and_then(|_| OpenOptions::new().create(true).append(true).open(path))
+                .and_then(|mut f| writeln!(f, "{}", revert));
+            if let Err(e) = written {
+                log::warn!("lock: cannot write {}: {}", path.display(), e);
+            }
+        }
+        let mut reverts = self.reverts.lock().unwrap();
+        if reverts.len() == MAX_REVERTS {
+            reverts.pop_front();
+        }
+        reverts.push_back(revert);
+    }
+}
+
+impl<B: AudioBackend> AudioBackend for LockingBackend<B> {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        let mut streams = self.inner.list_streams()?;
+        let mut locked = self.locked.lock().unwrap();
+        locked.retain(|id, _| streams.iter().any(|s| s.id == *id));
+
+        let mut found = Vec::new();
+        for s in streams.iter_mut() {
+            let Some(want) = locked.get(&s.id) else { continue };
+            // the stream may be gone since it was listed; the others are
+            // still worth showing and reverting
+            if (s.volume_01 - want.volume_01).abs() > EPSILON {
+                match self.inner.set_volume(s.id, want.volume_01) {
+                    Ok(()) => {
+                        found.push((s.id, s.name.clone(), Change::Volume { from: want.volume_01, to: s.volume_01 }));
+                        s.volume_01 = want.volume_01;
+                    }
+                    Err(e) => log::warn!("lock: cannot revert the volume of {} (#{}): {}", s.name, s.id, e),
+                }
+            }
+            if s.mute != want.mute {
+                match self.inner.set_mute(s.id, want.mute) {
+                    Ok(()) => {
+                        found.push((s.id, s.name.clone(), Change::Mute { from: want.mute, to: s.mute }));
+                        s.mute = want.mute;
+                    }
+                    Err(e) => log::warn!("lock: cannot revert the mute of {} (#{}): {}", s.name, s.id, e),
+                }
+            }
+        }
+        drop(locked);
+
+        for (stream_id, app, change) in found {
+            self.record(Revert { at: SystemTime::now(), stream_id, app, change });
+        }
+        Ok(streams)
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        if let Some(l) = self.locked.lock().unwrap().get_mut(&stream_id) {
+            l.volume_01 = vol_01.clamp(0.0, 1.0);
+        }
+        self.inner.set_volume(stream_id, vol_01)
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        if let Some(l) = self.locked.lock().unwrap().get_mut(&stream_id) {
+            l.mute = mute;
+        }
+        self.inner.set_mute(stream_id, mute)
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        self.inner.list_devices()
+    }
+
+    fn set_device_volume(&self, device_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.inner.set_device_volume(device_id, vol_01)
+    }
+
+    fn set_device_mute(&self, device_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.inner.set_device_mute(device_id, mute)
+    }
+
+    fn move_stream(&self, stream_id: u32, device_id: u32) -> Result<(), AudioError> {
+        self.inner.move_stream(stream_id, device_id)
+    }
+
+    fn subscribe(&self) -> Result<Subscription, AudioError> {
+        self.inner.subscribe()
+    }
+}
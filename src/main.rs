@@ -1,28 +1,105 @@
 // src/main.rs
 
 mod audio;
+mod bar;
+mod cache;
+mod cli;
+mod config;
+mod daemon;
+mod desktop;
+mod focus;
+mod groups;
+mod i18n;
+mod icons;
+mod keys;
+mod limits;
+mod lock;
+mod memory;
+mod notify;
+mod osd;
+mod pipewire_cli;
 mod pulseaudio_cli;
+mod rules;
+mod scenes;
+mod selector;
+mod tray;
 mod ui;
+mod values;
+mod watch;
+mod worker;
+mod writes;
+mod xdg;
+
+use audio::AudioBackend;
+use limits::{CappedBackend, Limits};
+use pipewire_cli::PipeWireCli;
+use pulseaudio_cli::PulseAudioCli;
 
 fn main() {
+    env_logger::init();
+
     let args: Vec<String> = std::env::args().collect();
-    println!("DEBUG: raw args = {:?}", args);
+    log::debug!("raw args = {:?}", args);
 
     if args.len() > 1 {
-        println!("DEBUG: first argument = {}", args[1]);
         match args[1].as_str() {
             "--popup" => {
-                println!("DEBUG: entering popup mode");
+                log::debug!("entering popup mode");
                 ui::run_popup_ui();
             }
-            _ => {
-                eprintln!("Unknown option {}", args[1]);
-                std::process::exit(1);
-            }
+            "daemon" => std::process::exit(run_daemon(&args[2..])),
+            "tray" => std::process::exit(tray::run_tray(cli_backend())),
+            "--help" | "-h" => println!("{}", cli::usage()),
+            _ => std::process::exit(run_cli(&args[1..])),
         }
     } else {
-        println!("DEBUG: entering full mode");
+        log::debug!("entering full mode");
         ui::run_full_ui();
     }
 }
 
+// A running daemon already holds the state; otherwise pactl, which also
+// drives PipeWire through pipewire-pulse, is preferred.
+fn cli_backend() -> Box<dyn AudioBackend> {
+    if let Some(client) = daemon::DaemonClient::connect() {
+        log::debug!("using the running daemon");
+        Box::new(client)
+    } else if !PulseAudioCli::available() && PipeWireCli::available() {
+        Box::new(CappedBackend::new(PipeWireCli, Limits::load_default()))
+    } else {
+        Box::new(CappedBackend::new(PulseAudioCli, Limits::load_default()))
+    }
+}
+
+fn run_cli(args: &[String]) -> i32 {
+    let result = cli::parse_args(args).and_then(|inv| {
+        let backend = cli_backend();
+        cli::run(&inv, backend.as_ref(), &mut std::io::stdout().lock())
+    });
+    match result {
+        Ok(feedback) => {
+            // one bubble; with several streams changed the last one wins
+            if let Some(msg) = feedback.last()
+                && let Some(notifier) = notify::Notifier::connect()
+            {
+                notifier.show(msg);
+            }
+            0
+        }
+        Err(e) => {
+            eprintln!("wlvolctl: {}", e);
+            e.exit_code()
+        }
+    }
+}
+
+fn run_daemon(args: &[String]) -> i32 {
+    match args {
+        [] => daemon::run_daemon(false),
+        [flag] if flag == "--osd" => daemon::run_daemon(true),
+        _ => {
+            eprintln!("wlvolctl: {}\n\n{}", tr!("daemon takes only --osd"), cli::usage());
+            2
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ini::Ini;
+use thiserror::Error;
+
+use crate::audio::Stream;
+use crate::values::{format_bool, format_percent, parse_bool, parse_percent};
+use crate::xdg;
+
+#[derive(Error, Debug)]
+pub enum MemoryError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("volumes: {0}")]
+    Parse(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Remembered {
+    pub volume_01: f32,
+    pub mute: bool,
+}
+
+/// Last volume and mute state per application, so that an app which closes
+/// and reopens its stream (next track, restart) comes back where it was:
+///
+/// ```ini
+/// [firefox]
+/// volume = 40%
+/// mute = no
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct VolumeMemory {
+    apps: HashMap<String, Remembered>,
+}
+
+fn app_key(s: &Stream) -> String {
+    s.name.to_lowercase()
+}
+
+impl VolumeMemory {
+    /// `$XDG_STATE_HOME/wlvolctl/volumes`
+    pub fn default_path() -> PathBuf {
+        xdg::state_dir().join("volumes")
+    }
+
+    /// Loads remembered volumes; a missing file means none.
+    pub fn load(path: &Path) -> Result<VolumeMemory, MemoryError> {
+        match fs::read_to_string(path) {
+            Ok(text) => VolumeMemory::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(VolumeMemory::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<VolumeMemory, MemoryError> {
+        let conf = Ini::load_from_str(text).map_err(|e| MemoryError::Parse(e.to_string()))?;
+        let mut memory = VolumeMemory::default();
+        for (section, props) in conf.iter() {
+            let Some(app) = section else { continue };
+            let volume = props.get("volume").and_then(parse_percent);
+            let mute = props.get("mute").map_or(Some(false), parse_bool);
+            match (volume, mute) {
+                (Some(volume_01), Some(mute)) => {
+                    memory.apps.insert(app.to_lowercase(), Remembered { volume_01, mute });
+                }
+                _ => return Err(MemoryError::Parse(format!("[{}]: bad volume or mute", app))),
+            }
+        }
+        Ok(memory)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), MemoryError> {
+        let mut conf = Ini::new();
+        let mut apps: Vec<_> = self.apps.iter().collect();
+        apps.sort_by(|a, b| a.0.cmp(b.0));
+        for (app, r) in apps {
+            conf.with_section(Some(app.as_str()))
+                .set("volume", format_percent(r.volume_01))
+                .set("mute", format_bool(r.mute));
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        conf.write_to_file(path)?;
+        Ok(())
+    }
+
+    /// Records the stream's current state. Returns whether anything changed.
+    pub fn remember(&mut self, s: &Stream) -> bool {
+        let new = Remembered { volume_01: s.volume_01, mute: s.mute };
+        self.apps.insert(app_key(s), new) != Some(new)
+    }
+
+    pub fn recall(&self, s: &Stream) -> Option<Remembered> {
+        self.apps.get(&app_key(s)).copied()
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use gtk4::gio::{self, BusType, Cancellable, DBusCallFlags, DBusConnection};
+use gtk4::glib::{Variant, VariantTy};
+use gtk4::prelude::*;
+
+use crate::osd::OsdMessage;
+use crate::xdg;
+
+const APP_NAME: &str = "wlvolctl";
+const EXPIRE_MS: i32 = 1500;
+const CALL_TIMEOUT_MS: i32 = 2000;
+
+/// Shows [`OsdMessage`]s through `org.freedesktop.Notifications`. Each new
+/// message replaces the previous bubble, also across processes: the last
+/// notification id is kept in the runtime directory.
+pub struct Notifier {
+    conn: DBusConnection,
+    id_path: PathBuf,
+}
+
+impl Notifier {
+    pub fn connect() -> Option<Notifier> {
+        match gio::bus_get_sync(BusType::Session, Cancellable::NONE) {
+            Ok(conn) => Some(Notifier { conn, id_path: xdg::runtime_dir().join("osd-id") }),
+            Err(e) => {
+                log::warn!("osd: no session bus: {}", e);
+                None
+            }
+        }
+    }
+
+    fn last_id(&self) -> u32 {
+        fs::read_to_string(&self.id_path).ok().and_then(|s| s.trim().parse().ok()).unwrap_or(0)
+    }
+
+    fn save_id(&self, id: u32) {
+        let saved = self.id_path.parent().map_or(Ok(()), fs::create_dir_all).and_then(|_| fs::write(&self.id_path, id.to_string()));
+        if let Err(e) = saved {
+            log::warn!("osd: cannot write {}: {}", self.id_path.display(), e);
+        }
+    }
+
+    pub fn show(&self, msg: &OsdMessage) {
+        let mut hints: HashMap<String, Variant> = HashMap::new();
+        // drawn as a progress bar by dunst, mako and others
+        hints.insert("value".into(), msg.percent().clamp(0, 100).to_variant());
+        hints.insert("x-canonical-private-synchronous".into(), APP_NAME.to_variant());
+        hints.insert("transient".into(), true.to_variant());
+
+        let params = (
+            APP_NAME,
+            self.last_id(),
+            msg.icon.as_str(),
+            msg.title.as_str(),
+            msg.body(),
+            Vec::<String>::new(),
+            hints,
+            EXPIRE_MS,
+        )
+            .to_variant();
+        let reply = self.conn.call_sync(
+            Some("org.freedesktop.Notifications"),
+            "/org/freedesktop/Notifications",
+            "org.freedesktop.Notifications",
+            "Notify",
+            Some(&params),
+            Some(VariantTy::new("(u)").unwrap()),
+            DBusCallFlags::NONE,
+            CALL_TIMEOUT_MS,
+            Cancellable::NONE,
+        );
+        match reply.map(|r| r.get::<(u32,)>()) {
+            Ok(Some((id,))) => self.save_id(id),
+            Ok(None) => {}
+            Err(e) => log::warn!("osd: notification failed: {}", e),
+        }
+    }
+}
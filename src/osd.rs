@@ -0,0 +1,60 @@
+use crate::audio::{Device, Stream};
+use crate::desktop::AppIndex;
+use crate::tr;
+use crate::values::format_percent;
+
+/// What the on-screen display shows after a volume change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OsdMessage {
+    pub icon: String,
+    pub title: String,
+    pub volume_01: f32,
+    pub muted: bool,
+}
+
+/// Themed volume icon for a level.
+pub fn level_icon(volume_01: f32, muted: bool) -> &'static str {
+    if muted || volume_01 <= 0.0 {
+        "audio-volume-muted"
+    } else if volume_01 < 0.34 {
+        "audio-volume-low"
+    } else if volume_01 < 0.67 {
+        "audio-volume-medium"
+    } else {
+        "audio-volume-high"
+    }
+}
+
+impl OsdMessage {
+    /// Shows the app's icon, which `apps` also finds for streams that do
+    /// not name one, else the level icon.
+    pub fn for_stream(s: &Stream, apps: Option<&AppIndex>) -> Self {
+        let icon = match apps {
+            Some(apps) => apps.icon_for(s),
+            None => s.icon_name.clone(),
+        };
+        OsdMessage {
+            icon: icon.unwrap_or_else(|| level_icon(s.volume_01, s.mute).to_string()),
+            title: s.name.clone(),
+            volume_01: s.volume_01,
+            muted: s.mute,
+        }
+    }
+
+    pub fn for_device(d: &Device) -> Self {
+        OsdMessage {
+            icon: level_icon(d.volume_01, d.mute).to_string(),
+            title: d.description.clone(),
+            volume_01: d.volume_01,
+            muted: d.mute,
+        }
+    }
+
+    pub fn body(&self) -> String {
+        if self.muted { tr!("Muted") } else { format_percent(self.volume_01) }
+    }
+
+    pub fn percent(&self) -> i32 {
+        (self.volume_01 * 100.0).round() as i32
+    }
+}
@@ -58,6 +58,12 @@ impl AudioBackend for PipeWireCli {
                         icon_name: None,
                         volume_01: vol,
                         mute: false,
+                        device_id: None,
+                        binary: None,
+                        role: None,
+                        media_name: None,
+                        app_id: None,
+                        pid: None,
                         backend_tag: BackendTag::PipeWire,
                     });
                 }
@@ -1,7 +1,13 @@
-use std::process::Command;
+use std::io::{BufRead, BufReader};
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use once_cell::sync::Lazy;
 use regex::Regex;
 
-use crate::audio::{AudioBackend, AudioError, BackendTag, Stream};
+use crate::audio::{AudioBackend, AudioError, BackendEvent, BackendTag, Device, Stream, Subscription};
 
 pub struct PulseAudioCli;
 
@@ -15,91 +21,254 @@ impl PulseAudioCli {
     }
 }
 
-impl AudioBackend for PulseAudioCli {
-    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
-        let out = Command::new("pactl")
-            .args(["list", "sink-inputs"])
-            .output()
-            .map_err(|e| AudioError::CommandFailed(e.to_string()))?;
+fn pactl_output(args: &[&str]) -> Result<String, AudioError> {
+    let out = Command::new("pactl")
+        .args(args)
+        .output()
+        .map_err(|e| AudioError::CommandFailed(e.to_string()))?;
+
+    if !out.status.success() {
+        return Err(AudioError::CommandFailed(format!("pactl {} failed", args.join(" "))));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).into_owned())
+}
 
-        if !out.status.success() {
-            return Err(AudioError::CommandFailed("pactl list failed".into()));
+fn pactl_status(args: &[&str], what: &str) -> Result<(), AudioError> {
+    let status = Command::new("pactl")
+        .args(args)
+        .status()
+        .map_err(|e| AudioError::CommandFailed(e.to_string()))?;
+    if status.success() { Ok(()) } else { Err(AudioError::CommandFailed(format!("pactl {} failed", what))) }
+}
+
+/// Parses the output of `pactl list sink-inputs`.
+pub fn parse_sink_inputs(text: &str) -> Vec<Stream> {
+    let re_id = Regex::new(r"^Sink Input #(\d+)").unwrap();
+    let re_sink = Regex::new(r"^\s*Sink:\s*(\d+)").unwrap();
+    let re_prop = Regex::new(r#"^\s*([A-Za-z0-9_.\-]+)\s*=\s*"(.*)"\s*$"#).unwrap();
+    let re_vol = Regex::new(r"(\d+)%").unwrap();
+    let re_mute = Regex::new(r"Mute:\s*(yes|no)").unwrap();
+
+    let mut streams = Vec::new();
+    let mut cur: Option<Stream> = None;
+    let mut cur_vol: Option<f32> = None;
+
+    // Streams without a name or volume line are skipped.
+    let mut flush = |cur: Option<Stream>, vol: Option<f32>| {
+        if let (Some(mut s), Some(vol)) = (cur, vol)
+            && !s.name.is_empty()
+        {
+            s.volume_01 = vol;
+            streams.push(s);
         }
+    };
 
-        let text = String::from_utf8_lossy(&out.stdout);
-        let re_id = Regex::new(r"^Sink Input #(\d+)").unwrap();
-        let re_name = Regex::new(r#"application\.name\s*=\s*"([^"]+)""#).unwrap();
-        let re_vol = Regex::new(r"(\d+)%").unwrap();
-        let re_mute = Regex::new(r"Mute:\s*(yes|no)").unwrap();
-
-        let mut streams = Vec::new();
-        let mut cur_id: Option<u32> = None;
-        let mut cur_name: Option<String> = None;
-        let mut cur_vol: Option<f32> = None;
-        let mut cur_mute: bool = false;
-
-        for line in text.lines() {
-            if let Some(c) = re_id.captures(line) {
-                // flush previous
-                if let (Some(id), Some(name), Some(vol)) = (cur_id, cur_name.clone(), cur_vol) {
-                    streams.push(Stream {
-                        id,
-                        name,
-                        icon_name: None,
-                        volume_01: vol,
-                        mute: cur_mute,
-                        backend_tag: BackendTag::PulseAudio,
-                    });
+    for line in text.lines() {
+        if let Some(c) = re_id.captures(line) {
+            flush(cur.take(), cur_vol.take());
+            cur = c[1].parse().ok().map(|id| Stream {
+                id,
+                name: String::new(),
+                icon_name: None,
+                volume_01: 0.0,
+                mute: false,
+                device_id: None,
+                binary: None,
+                role: None,
+                media_name: None,
+                app_id: None,
+                pid: None,
+                backend_tag: BackendTag::PulseAudio,
+            });
+            continue;
+        }
+        let Some(s) = cur.as_mut() else { continue };
+        if let Some(c) = re_sink.captures(line) {
+            s.device_id = c[1].parse().ok();
+            continue;
+        }
+        // Properties come first: media names may contain a '%'.
+        if let Some(c) = re_prop.captures(line) {
+            let value = c[2].to_string();
+            match &c[1] {
+                "application.name" => s.name = value,
+                "application.icon_name" => s.icon_name = Some(value),
+                "application.process.binary" => s.binary = Some(value),
+                "application.process.id" => s.pid = value.parse().ok(),
+                "media.role" => s.role = Some(value),
+                "media.name" => s.media_name = Some(value),
+                "pipewire.access.portal.app_id" => s.app_id = Some(value),
+                "application.id" => {
+                    s.app_id.get_or_insert(value);
                 }
-                cur_id = c[1].parse().ok();
-                cur_name = None;
-                cur_vol = None;
-                cur_mute = false;
-                continue;
-            }
-            if let Some(c) = re_name.captures(line) {
-                cur_name = Some(c[1].to_string());
-                continue;
-            }
-            if let Some(c) = re_vol.captures(line) {
-                let pct: f32 = c[1].parse().unwrap_or(0.0);
-                cur_vol = Some((pct / 100.0).clamp(0.0, 1.0));
-                continue;
-            }
-            if let Some(c) = re_mute.captures(line) {
-                cur_mute = &c[1] == "yes";
-                continue;
+                _ => {}
             }
+            continue;
+        }
+        if cur_vol.is_none()
+            && let Some(c) = re_vol.captures(line)
+        {
+            let pct: f32 = c[1].parse().unwrap_or(0.0);
+            cur_vol = Some((pct / 100.0).clamp(0.0, 1.0));
+            continue;
+        }
+        if let Some(c) = re_mute.captures(line) {
+            s.mute = &c[1] == "yes";
+            continue;
         }
-        if let (Some(id), Some(name), Some(vol)) = (cur_id, cur_name, cur_vol) {
-            streams.push(Stream {
+    }
+    flush(cur, cur_vol);
+
+    streams
+}
+
+/// Parses the output of `pactl list sinks`.
+pub fn parse_sinks(text: &str) -> Vec<Device> {
+    let re_id = Regex::new(r"^Sink #(\d+)").unwrap();
+    let re_name = Regex::new(r"^\s*Name:\s*(.+)$").unwrap();
+    let re_desc = Regex::new(r"^\s*Description:\s*(.+)$").unwrap();
+    // Only the channel volume line; "Base Volume:" carries a percentage too.
+    let re_vol = Regex::new(r"^\s*Volume:.*?(\d+)%").unwrap();
+    let re_mute = Regex::new(r"^\s*Mute:\s*(yes|no)").unwrap();
+
+    let mut devices = Vec::new();
+    let mut cur: Option<Device> = None;
+
+    for line in text.lines() {
+        if let Some(c) = re_id.captures(line) {
+            devices.extend(cur.take());
+            cur = c[1].parse().ok().map(|id| Device {
                 id,
-                name,
-                icon_name: None,
-                volume_01: vol,
-                mute: cur_mute,
+                name: String::new(),
+                description: String::new(),
+                volume_01: 0.0,
+                mute: false,
+                is_default: false,
                 backend_tag: BackendTag::PulseAudio,
             });
+            continue;
         }
+        let Some(d) = cur.as_mut() else { continue };
+        if let Some(c) = re_name.captures(line) {
+            d.name = c[1].trim().to_string();
+        } else if let Some(c) = re_desc.captures(line) {
+            d.description = c[1].trim().to_string();
+        } else if let Some(c) = re_vol.captures(line) {
+            let pct: f32 = c[1].parse().unwrap_or(0.0);
+            d.volume_01 = (pct / 100.0).clamp(0.0, 1.0);
+        } else if let Some(c) = re_mute.captures(line) {
+            d.mute = &c[1] == "yes";
+        }
+    }
+    devices.extend(cur);
+
+    devices
+}
 
-        Ok(streams)
+/// The "Default Sink:" name from `pactl info`.
+pub fn parse_default_sink(text: &str) -> Option<String> {
+    text.lines()
+        .find_map(|line| line.strip_prefix("Default Sink:"))
+        .map(|name| name.trim().to_string())
+}
+
+static RE_EVENT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^Event '(\w+)' on ([\w-]+)(?: #(\d+))?").unwrap());
+
+/// Parses one line of `pactl subscribe`, e.g. "Event 'new' on sink-input #12".
+pub fn parse_event(line: &str) -> Option<BackendEvent> {
+    let c = RE_EVENT.captures(line.trim())?;
+    let id = c.get(3).and_then(|m| m.as_str().parse().ok());
+    match (&c[2], &c[1], id) {
+        ("sink-input", "new", Some(id)) => Some(BackendEvent::StreamAdded(id)),
+        ("sink-input", "remove", Some(id)) => Some(BackendEvent::StreamRemoved(id)),
+        ("sink-input", "change", Some(id)) => Some(BackendEvent::StreamChanged(id)),
+        ("sink", _, Some(id)) => Some(BackendEvent::DeviceChanged(id)),
+        ("server", _, _) => Some(BackendEvent::ServerChanged),
+        _ => None,
+    }
+}
+
+impl AudioBackend for PulseAudioCli {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        let text = pactl_output(&["list", "sink-inputs"])?;
+        Ok(parse_sink_inputs(&text))
     }
 
     fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
         let pct = (vol_01.clamp(0.0, 1.0) * 100.0).round() as i32;
-        let status = Command::new("pactl")
-            .args(["set-sink-input-volume", &stream_id.to_string(), &format!("{}%", pct)])
-            .status()
-            .map_err(|e| AudioError::CommandFailed(e.to_string()))?;
-        if status.success() { Ok(()) } else { Err(AudioError::CommandFailed("pactl set-volume failed".into())) }
+        pactl_status(
+            &["set-sink-input-volume", &stream_id.to_string(), &format!("{}%", pct)],
+            "set-volume",
+        )
     }
 
     fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
-        let status = Command::new("pactl")
-            .args(["set-sink-input-mute", &stream_id.to_string(), if mute { "1" } else { "0" }])
-            .status()
+        pactl_status(
+            &["set-sink-input-mute", &stream_id.to_string(), if mute { "1" } else { "0" }],
+            "set-mute",
+        )
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        let text = pactl_output(&["list", "sinks"])?;
+        let mut devices = parse_sinks(&text);
+        if let Some(default) = parse_default_sink(&pactl_output(&["info"])?) {
+            for d in devices.iter_mut() {
+                d.is_default = d.name == default;
+            }
+        }
+        Ok(devices)
+    }
+
+    fn set_device_volume(&self, device_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        let pct = (vol_01.clamp(0.0, 1.0) * 100.0).round() as i32;
+        pactl_status(
+            &["set-sink-volume", &device_id.to_string(), &format!("{}%", pct)],
+            "set-sink-volume",
+        )
+    }
+
+    fn set_device_mute(&self, device_id: u32, mute: bool) -> Result<(), AudioError> {
+        pactl_status(
+            &["set-sink-mute", &device_id.to_string(), if mute { "1" } else { "0" }],
+            "set-sink-mute",
+        )
+    }
+
+    fn move_stream(&self, stream_id: u32, device_id: u32) -> Result<(), AudioError> {
+        pactl_status(
+            &["move-sink-input", &stream_id.to_string(), &device_id.to_string()],
+            "move-sink-input",
+        )
+    }
+
+    // Dropping the subscription kills pactl, which ends the reader.
+    fn subscribe(&self) -> Result<Subscription, AudioError> {
+        let mut child = Command::new("pactl")
+            .arg("subscribe")
+            .stdout(Stdio::piped())
+            .spawn()
             .map_err(|e| AudioError::CommandFailed(e.to_string()))?;
-        if status.success() { Ok(()) } else { Err(AudioError::CommandFailed("pactl set-mute failed".into())) }
+        let stdout = child.stdout.take().ok_or(AudioError::NotAvailable)?;
+
+        let child = Arc::new(Mutex::new(child));
+        let (tx, rx) = mpsc::channel();
+        let reader_child = Arc::clone(&child);
+        thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if let Some(ev) = parse_event(&line)
+                    && tx.send(ev).is_err()
+                {
+                    break;
+                }
+            }
+            let mut child = reader_child.lock().unwrap();
+            let _ = child.kill();
+            let _ = child.wait();
+        });
+        Ok(Subscription::with_stop(rx, move || {
+            let _ = child.lock().unwrap().kill();
+        }))
     }
 }
-
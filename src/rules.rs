@@ -0,0 +1,259 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ini::Ini;
+use regex::Regex;
+use thiserror::Error;
+
+use crate::audio::{AudioBackend, AudioError, Stream};
+use crate::values::{glob_to_regex, parse_bool, parse_percent};
+use crate::xdg;
+
+#[derive(Error, Debug)]
+pub enum RuleError {
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("rules: {0}")]
+    Parse(String),
+    #[error("rule [{rule}]: {msg}")]
+    Invalid { rule: String, msg: String },
+}
+
+/// Stream property a rule can match on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Name,
+    Binary,
+    Role,
+    Media,
+    Flatpak,
+}
+
+impl Field {
+    fn from_key(key: &str) -> Option<Field> {
+        match key {
+            "name" => Some(Field::Name),
+            "binary" => Some(Field::Binary),
+            "role" => Some(Field::Role),
+            "media" => Some(Field::Media),
+            "flatpak" => Some(Field::Flatpak),
+            _ => None,
+        }
+    }
+
+    fn value<'a>(&self, s: &'a Stream) -> Option<&'a str> {
+        match self {
+            Field::Name => Some(s.name.as_str()),
+            Field::Binary => s.binary.as_deref(),
+            Field::Role => s.role.as_deref(),
+            Field::Media => s.media_name.as_deref(),
+            Field::Flatpak => s.app_id.as_deref(),
+        }
+    }
+}
+
+/// What happens to a stream once its rules matched. Later rules override
+/// earlier ones field by field.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Actions {
+    pub volume: Option<f32>,
+    pub mute: Option<bool>,
+    /// Glob matched against device name or description.
+    pub device: Option<String>,
+    pub hide: bool,
+}
+
+#[derive(Debug)]
+pub struct Rule {
+    pub name: String,
+    matchers: Vec<(Field, Regex)>,
+    pub actions: Actions,
+}
+
+impl Rule {
+    pub fn matches(&self, s: &Stream) -> bool {
+        self.matchers
+            .iter()
+            .all(|(field, re)| field.value(s).is_some_and(|v| re.is_match(v)))
+    }
+}
+
+/// Rules are INI sections, one per rule, applied in file order:
+///
+/// ```ini
+/// [chrome events start muted]
+/// binary = chrom*
+/// role = event
+/// mute = yes
+///
+/// [steam games on hdmi]
+/// binary = steam_app_*
+/// volume = 60%
+/// device = *hdmi*
+/// ```
+///
+/// Match keys are `name`, `binary`, `role`, `media` and `flatpak`, all
+/// case-insensitive globs. Actions are `volume`, `mute`, `device` and `hide`.
+#[derive(Debug, Default)]
+pub struct RuleSet {
+    rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    /// `$XDG_CONFIG_HOME/wlvolctl/rules`
+    pub fn default_path() -> PathBuf {
+        xdg::config_dir().join("rules")
+    }
+
+    /// Loads a rule file; a missing file is an empty rule set.
+    pub fn load(path: &Path) -> Result<RuleSet, RuleError> {
+        match fs::read_to_string(path) {
+            Ok(text) => RuleSet::parse(&text),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(RuleSet::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<RuleSet, RuleError> {
+        let conf = Ini::load_from_str(text).map_err(|e| RuleError::Parse(e.to_string()))?;
+        let mut rules = Vec::new();
+
+        for (section, props) in conf.iter() {
+            let Some(name) = section else {
+                if props.is_empty() {
+                    continue;
+                }
+                return Err(RuleError::Parse("keys outside of a [rule] section".into()));
+            };
+            let invalid = |msg: String| RuleError::Invalid { rule: name.to_string(), msg };
+
+            let mut rule = Rule { name: name.to_string(), matchers: Vec::new(), actions: Actions::default() };
+            for (key, value) in props.iter() {
+                if let Some(field) = Field::from_key(key) {
+                    rule.matchers.push((field, glob_to_regex(value)));
+                    continue;
+                }
+                match key {
+                    "volume" => {
+                        rule.actions.volume =
+                            Some(parse_percent(value).ok_or_else(|| invalid(format!("bad volume {:?}", value)))?)
+                    }
+                    "mute" => {
+                        rule.actions.mute = Some(parse_bool(value).ok_or_else(|| invalid(format!("bad mute {:?}", value)))?)
+                    }
+                    "hide" => rule.actions.hide = parse_bool(value).ok_or_else(|| invalid(format!("bad hide {:?}", value)))?,
+                    "device" => rule.actions.device = Some(value.to_string()),
+                    _ => return Err(invalid(format!("unknown key {:?}", key))),
+                }
+            }
+            if rule.matchers.is_empty() {
+                return Err(invalid("no match keys (use `name = *` to match every stream)".into()));
+            }
+            rules.push(rule);
+        }
+        Ok(RuleSet { rules })
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn actions_for(&self, s: &Stream) -> Actions {
+        let mut actions = Actions::default();
+        for rule in self.rules.iter().filter(|r| r.matches(s)) {
+            let a = &rule.actions;
+            if a.volume.is_some() {
+                actions.volume = a.volume;
+            }
+            if a.mute.is_some() {
+                actions.mute = a.mute;
+            }
+            if a.device.is_some() {
+                actions.device = a.device.clone();
+            }
+            actions.hide |= a.hide;
+        }
+        actions
+    }
+}
+
+/// Runs a rule set against every stream the first time it shows up.
+///
+/// Streams already playing when the engine sees its first list are only
+/// checked for `hide`; their volume, mute and routing are left alone so
+/// that starting wlvolctl does not reset running apps.
+pub struct RuleEngine {
+    rules: RuleSet,
+    seen: HashSet<u32>,
+    hidden: HashSet<u32>,
+    primed: bool,
+}
+
+impl RuleEngine {
+    pub fn new(rules: RuleSet) -> Self {
+        RuleEngine { rules, seen: HashSet::new(), hidden: HashSet::new(), primed: false }
+    }
+
+    /// Swaps the rule set. Hiding is re-evaluated for every known stream.
+    pub fn set_rules(&mut self, rules: RuleSet) {
+        self.rules = rules;
+        self.seen.clear();
+        self.hidden.clear();
+        self.primed = false;
+    }
+
+    pub fn process<B: AudioBackend + ?Sized>(&mut self, streams: &[Stream], backend: &B) {
+        let mut devices = None;
+
+        for s in streams {
+            if !self.seen.insert(s.id) {
+                continue;
+            }
+            let actions = self.rules.actions_for(s);
+            if actions.hide {
+                self.hidden.insert(s.id);
+            }
+            if !self.primed {
+                continue;
+            }
+
+            if let Some(pattern) = &actions.device {
+                let devices = devices.get_or_insert_with(|| backend.list_devices().unwrap_or_default());
+                let re = glob_to_regex(pattern);
+                match devices.iter().find(|d| re.is_match(&d.name) || re.is_match(&d.description)) {
+                    Some(d) => warn_on_err(s, "move", backend.move_stream(s.id, d.id)),
+                    None => log::warn!("rules: no device matches {:?} for {}", pattern, s.name),
+                }
+            }
+            if let Some(vol) = actions.volume {
+                warn_on_err(s, "set volume", backend.set_volume(s.id, vol));
+            }
+            if let Some(mute) = actions.mute {
+                warn_on_err(s, "set mute", backend.set_mute(s.id, mute));
+            }
+        }
+
+        self.seen.retain(|id| streams.iter().any(|s| s.id == *id));
+        self.hidden.retain(|id| streams.iter().any(|s| s.id == *id));
+        self.primed = true;
+    }
+
+    pub fn actions_for(&self, s: &Stream) -> Actions {
+        self.rules.actions_for(s)
+    }
+
+    pub fn is_hidden(&self, stream_id: u32) -> bool {
+        self.hidden.contains(&stream_id)
+    }
+}
+
+fn warn_on_err(s: &Stream, what: &str, result: Result<(), AudioError>) {
+    if let Err(e) = result {
+        log::warn!("rules: failed to {} for {} (#{}): {}", what, s.name, s.id, e);
+    }
+}
@@ -0,0 +1,329 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use ini::Ini;
+use thiserror::Error;
+
+use crate::audio::{AudioBackend, AudioError, Device};
+use crate::values::{format_bool, format_percent, parse_bool, parse_percent};
+use crate::xdg;
+
+const STREAM_PREFIX: &str = "stream ";
+const DEVICE_PREFIX: &str = "device ";
+const FADE_STEP: Duration = Duration::from_millis(40);
+
+#[derive(Error, Debug)]
+pub enum SceneError {
+    #[error("invalid scene name: {0:?}")]
+    InvalidName(String),
+    #[error("scene not found: {0}")]
+    NotFound(String),
+    #[error("scene {name}: {msg}")]
+    Parse { name: String, msg: String },
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Audio(#[from] AudioError),
+}
+
+/// Saved state of one application's stream. Streams are matched by
+/// application name since stream ids do not survive a restart.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneStream {
+    pub app: String,
+    pub volume_01: f32,
+    pub mute: bool,
+    /// Name of the device the stream is routed to.
+    pub device: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneDevice {
+    pub name: String,
+    pub volume_01: f32,
+    pub mute: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Scene {
+    pub name: String,
+    pub streams: Vec<SceneStream>,
+    pub devices: Vec<SceneDevice>,
+}
+
+// Backends without device support still get stream-only scenes.
+fn devices_or_empty<B: AudioBackend + ?Sized>(backend: &B) -> Result<Vec<Device>, AudioError> {
+    match backend.list_devices() {
+        Err(AudioError::NotAvailable) => Ok(Vec::new()),
+        other => other,
+    }
+}
+
+#[derive(Debug)]
+enum Target {
+    Stream(u32),
+    Device(u32),
+}
+
+struct Fade {
+    target: Target,
+    from: f32,
+    to: f32,
+    mute: bool,
+    /// Set once a change to the target failed.
+    skipped: bool,
+}
+
+impl Scene {
+    /// Snapshots every current stream and device. Streams are saved per
+    /// app: of several streams of one app, the first one listed gives the
+    /// level saved for all of them.
+    pub fn capture<B: AudioBackend + ?Sized>(name: &str, backend: &B) -> Result<Scene, SceneError> {
+        let streams = backend.list_streams()?;
+        let devices = devices_or_empty(backend)?;
+
+        let mut scene = Scene { name: name.to_string(), streams: Vec::new(), devices: Vec::new() };
+        for s in streams {
+            if scene.streams.iter().any(|e| e.app == s.name) {
+                continue;
+            }
+            let device = s
+                .device_id
+                .and_then(|id| devices.iter().find(|d| d.id == id))
+                .map(|d| d.name.clone());
+            scene.streams.push(SceneStream { app: s.name, volume_01: s.volume_01, mute: s.mute, device });
+        }
+        for d in devices {
+            scene.devices.push(SceneDevice { name: d.name, volume_01: d.volume_01, mute: d.mute });
+        }
+        Ok(scene)
+    }
+
+    /// Applies the scene, moving volumes linearly over `fade`. Apps and
+    /// devices that are not present right now are skipped, and so is one
+    /// that fails midway, such as a stream ending during the fade.
+    pub fn apply<B: AudioBackend + ?Sized>(&self, backend: &B, fade: Duration) -> Result<(), SceneError> {
+        let streams = backend.list_streams()?;
+        let devices = devices_or_empty(backend)?;
+        let mut fades = Vec::new();
+
+        for entry in &self.devices {
+            match devices.iter().find(|d| d.name == entry.name) {
+                Some(d) => fades.push(Fade {
+                    target: Target::Device(d.id),
+                    from: d.volume_01,
+                    to: entry.volume_01,
+                    mute: entry.mute,
+                    skipped: false,
+                }),
+                None => log::debug!("scene {}: device {} not present", self.name, entry.name),
+            }
+        }
+
+        for entry in &self.streams {
+            let target_dev = entry
+                .device
+                .as_ref()
+                .and_then(|name| devices.iter().find(|d| &d.name == name));
+            let mut matched = false;
+            for s in streams.iter().filter(|s| s.name == entry.app) {
+                matched = true;
+                if let Some(d) = target_dev
+                    && s.device_id != Some(d.id)
+                    && let Err(e) = backend.move_stream(s.id, d.id)
+                {
+                    log::warn!("scene {}: cannot move {} (#{}): {}", self.name, s.name, s.id, e);
+                    continue;
+                }
+                fades.push(Fade {
+                    target: Target::Stream(s.id),
+                    from: s.volume_01,
+                    to: entry.volume_01,
+                    mute: entry.mute,
+                    skipped: false,
+                });
+            }
+            if !matched {
+                log::debug!("scene {}: no stream for {}", self.name, entry.app);
+            }
+        }
+
+        let set_volume = |t: &Target, v: f32| match *t {
+            Target::Stream(id) => backend.set_volume(id, v),
+            Target::Device(id) => backend.set_device_volume(id, v),
+        };
+        let set_mute = |t: &Target, m: bool| match *t {
+            Target::Stream(id) => backend.set_mute(id, m),
+            Target::Device(id) => backend.set_device_mute(id, m),
+        };
+
+        // a target that fails is left alone from then on
+        let check = |f: &mut Fade, result: Result<(), AudioError>| {
+            if let Err(e) = result {
+                log::warn!("scene {}: skipping {:?}: {}", self.name, f.target, e);
+                f.skipped = true;
+            }
+        };
+
+        // Unmute before fading in, mute only once faded out.
+        for f in fades.iter_mut().filter(|f| !f.mute) {
+            check(f, set_mute(&f.target, false));
+        }
+
+        let steps = (fade.as_millis() / FADE_STEP.as_millis()).max(1) as u32;
+        for step in 1..=steps {
+            if step > 1 {
+                thread::sleep(FADE_STEP);
+            }
+            let t = step as f32 / steps as f32;
+            for f in fades.iter_mut().filter(|f| !f.skipped) {
+                check(f, set_volume(&f.target, f.from + (f.to - f.from) * t));
+            }
+        }
+
+        for f in fades.iter_mut().filter(|f| f.mute && !f.skipped) {
+            check(f, set_mute(&f.target, true));
+        }
+        Ok(())
+    }
+
+    fn to_ini(&self) -> Ini {
+        let mut conf = Ini::new();
+        for d in &self.devices {
+            conf.with_section(Some(format!("{}{}", DEVICE_PREFIX, d.name)))
+                .set("volume", format_percent(d.volume_01))
+                .set("mute", format_bool(d.mute));
+        }
+        for s in &self.streams {
+            let mut section = conf.with_section(Some(format!("{}{}", STREAM_PREFIX, s.app)));
+            section.set("volume", format_percent(s.volume_01)).set("mute", format_bool(s.mute));
+            if let Some(dev) = &s.device {
+                section.set("device", dev.as_str());
+            }
+        }
+        conf
+    }
+
+    fn from_ini(name: &str, conf: &Ini) -> Result<Scene, SceneError> {
+        let err = |msg: String| SceneError::Parse { name: name.to_string(), msg };
+        let mut scene = Scene { name: name.to_string(), streams: Vec::new(), devices: Vec::new() };
+
+        for (section, props) in conf.iter() {
+            let Some(section) = section else {
+                if props.is_empty() {
+                    continue;
+                }
+                return Err(err("keys outside of a [stream ...] or [device ...] section".into()));
+            };
+            let volume = match props.get("volume") {
+                Some(v) => parse_percent(v).ok_or_else(|| err(format!("[{}] bad volume {:?}", section, v)))?,
+                None => return Err(err(format!("[{}] missing volume", section))),
+            };
+            let mute = match props.get("mute") {
+                Some(v) => parse_bool(v).ok_or_else(|| err(format!("[{}] bad mute {:?}", section, v)))?,
+                None => false,
+            };
+
+            if let Some(app) = section.strip_prefix(STREAM_PREFIX) {
+                scene.streams.push(SceneStream {
+                    app: app.to_string(),
+                    volume_01: volume,
+                    mute,
+                    device: props.get("device").map(str::to_string),
+                });
+            } else if let Some(dev) = section.strip_prefix(DEVICE_PREFIX) {
+                scene.devices.push(SceneDevice { name: dev.to_string(), volume_01: volume, mute });
+            } else {
+                return Err(err(format!("unknown section [{}]", section)));
+            }
+        }
+        Ok(scene)
+    }
+}
+
+/// Scenes live as one INI file per scene, e.g. `scenes/meeting.ini`:
+///
+/// ```ini
+/// [stream Firefox]
+/// volume = 40%
+/// mute = no
+/// device = alsa_output.usb-headset.analog-stereo
+///
+/// [device alsa_output.usb-headset.analog-stereo]
+/// volume = 70%
+/// ```
+pub struct SceneStore {
+    dir: PathBuf,
+}
+
+impl SceneStore {
+    pub fn new<P: Into<PathBuf>>(dir: P) -> Self {
+        SceneStore { dir: dir.into() }
+    }
+
+    /// `$XDG_CONFIG_HOME/wlvolctl/scenes`
+    pub fn open_default() -> Self {
+        SceneStore::new(xdg::config_dir().join("scenes"))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, name: &str) -> Result<PathBuf, SceneError> {
+        if name.trim().is_empty() || name.starts_with('.') || name.contains(['/', '\0']) {
+            return Err(SceneError::InvalidName(name.to_string()));
+        }
+        Ok(self.dir.join(format!("{}.ini", name)))
+    }
+
+    pub fn list(&self) -> Result<Vec<String>, SceneError> {
+        let entries = match fs::read_dir(&self.dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e.into()),
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|s| s.to_str()) != Some("ini") {
+                    return None;
+                }
+                path.file_stem().and_then(|s| s.to_str()).map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        Ok(names)
+    }
+
+    pub fn load(&self, name: &str) -> Result<Scene, SceneError> {
+        let path = self.path_for(name)?;
+        if !path.exists() {
+            return Err(SceneError::NotFound(name.to_string()));
+        }
+        let conf = Ini::load_from_file(&path).map_err(|e| match e {
+            ini::Error::Io(e) => SceneError::Io(e),
+            ini::Error::Parse(e) => SceneError::Parse { name: name.to_string(), msg: e.to_string() },
+        })?;
+        Scene::from_ini(name, &conf)
+    }
+
+    pub fn save(&self, scene: &Scene) -> Result<(), SceneError> {
+        let path = self.path_for(&scene.name)?;
+        fs::create_dir_all(&self.dir)?;
+        scene.to_ini().write_to_file(path)?;
+        Ok(())
+    }
+
+    pub fn delete(&self, name: &str) -> Result<(), SceneError> {
+        let path = self.path_for(name)?;
+        match fs::remove_file(path) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(SceneError::NotFound(name.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use std::fmt;
+use std::str::FromStr;
+
+use regex::{Regex, RegexBuilder};
+use thiserror::Error;
+
+use crate::audio::{Device, Stream};
+use crate::focus::FocusedWindow;
+use crate::values::glob_to_regex;
+
+#[derive(Error, Debug, PartialEq)]
+#[error("bad selector {expr:?}: {msg}")]
+pub struct SelectorError {
+    pub expr: String,
+    pub msg: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Id,
+    Name,
+    Binary,
+    Role,
+    Media,
+    Flatpak,
+    Pid,
+    Device,
+    /// The role, for streams of the focused window's app only.
+    FocusedRole,
+}
+
+impl Key {
+    fn parse(s: &str) -> Option<Key> {
+        match s {
+            "id" => Some(Key::Id),
+            "name" | "app" => Some(Key::Name),
+            "binary" => Some(Key::Binary),
+            "role" => Some(Key::Role),
+            "media" => Some(Key::Media),
+            "flatpak" => Some(Key::Flatpak),
+            "pid" => Some(Key::Pid),
+            "device" => Some(Key::Device),
+            "focused-role" => Some(Key::FocusedRole),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Test {
+    All,
+    /// `=` and `:`, a case-insensitive glob. Numeric keys compare exactly.
+    Equals(Key, Regex),
+    /// `~=`, a case-insensitive regex search.
+    Search(Key, Regex),
+}
+
+#[derive(Debug, Clone)]
+struct Term {
+    negate: bool,
+    test: Test,
+}
+
+/// What selectors see besides the streams themselves. Callers fill in
+/// what [`Selector::needs_devices`] and [`Selector::needs_focus`] ask for.
+#[derive(Debug, Clone, Default)]
+pub struct Scope {
+    pub devices: Vec<Device>,
+    pub focused: Option<FocusedWindow>,
+}
+
+/// Picks streams by their properties instead of by temporary ids.
+///
+/// ```text
+/// all                      every stream
+/// 42                       stream id 42
+/// firefox                  application name, same as name=firefox
+/// name~=fire               regex search in the application name
+/// binary=mpv  role:music   glob match on a property
+/// pid=1234
+/// device=hdmi              any part of the device name or description
+/// !role=event              negation
+/// !focused-role:event      all but the event sounds of the focused app
+/// binary=mpv,role=music    both terms (and)
+/// binary=mpv|binary=vlc    either group (or)
+/// ```
+///
+/// Keys are `id`, `name`, `binary`, `role`, `media`, `flatpak`, `pid`,
+/// `device` and `focused-role`. The last one only has a value for streams
+/// of the focused window's app; when the focus is unknown it matches
+/// nothing.
+#[derive(Debug, Clone)]
+pub struct Selector {
+    source: String,
+    any_of: Vec<Vec<Term>>,
+}
+
+impl Selector {
+    pub fn parse(expr: &str) -> Result<Selector, SelectorError> {
+        let err = |msg: String| SelectorError { expr: expr.to_string(), msg };
+        let mut any_of = Vec::new();
+
+        for group in expr.split('|') {
+            let mut terms = Vec::new();
+            for raw in group.split(',') {
+                let raw = raw.trim();
+                if raw.is_empty() {
+                    return Err(err("empty term".into()));
+                }
+                let (negate, body) = match raw.strip_prefix('!') {
+                    Some(rest) => (true, rest.trim()),
+                    None => (false, raw),
+                };
+                terms.push(Term { negate, test: parse_test(body).map_err(err)? });
+            }
+            any_of.push(terms);
+        }
+        Ok(Selector { source: expr.to_string(), any_of })
+    }
+
+    fn uses(&self, key: Key) -> bool {
+        self.any_of
+            .iter()
+            .flatten()
+            .any(|t| matches!(t.test, Test::Equals(k, _) | Test::Search(k, _) if k == key))
+    }
+
+    /// Whether any term looks at devices, so callers can skip listing them.
+    pub fn needs_devices(&self) -> bool {
+        self.uses(Key::Device)
+    }
+
+    /// Whether any term looks at the focused window.
+    pub fn needs_focus(&self) -> bool {
+        self.uses(Key::FocusedRole)
+    }
+
+    pub fn matches(&self, s: &Stream, scope: &Scope) -> bool {
+        self.any_of
+            .iter()
+            .any(|terms| terms.iter().all(|t| eval(&t.test, s, scope) != t.negate))
+    }
+
+    pub fn select(&self, streams: &[Stream], scope: &Scope) -> Vec<Stream> {
+        streams.iter().filter(|s| self.matches(s, scope)).cloned().collect()
+    }
+}
+
+impl FromStr for Selector {
+    type Err = SelectorError;
+
+    fn from_str(s: &str) -> Result<Selector, SelectorError> {
+        Selector::parse(s)
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn parse_test(body: &str) -> Result<Test, String> {
+    if body == "all" {
+        return Ok(Test::All);
+    }
+    if body.chars().all(|c| c.is_ascii_digit()) {
+        return Ok(Test::Equals(Key::Id, glob_to_regex(body)));
+    }
+
+    // "~=" must be checked before "=", and ':' only when there is no '='
+    let (key, value, search) = if let Some((k, v)) = body.split_once("~=") {
+        (k, v, true)
+    } else if let Some((k, v)) = body.split_once('=') {
+        (k, v, false)
+    } else if let Some((k, v)) = body.split_once(':') {
+        (k, v, false)
+    } else {
+        ("name", body, false)
+    };
+
+    let key_name = key.trim();
+    let key = Key::parse(key_name).ok_or_else(|| format!("unknown key {:?}", key_name))?;
+    let value = value.trim();
+    if value.is_empty() {
+        return Err(format!("no value for {:?}", key_name));
+    }
+
+    if search {
+        let re = RegexBuilder::new(value)
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        return Ok(Test::Search(key, re));
+    }
+    if matches!(key, Key::Id | Key::Pid) && value.parse::<u32>().is_err() {
+        return Err(format!("{} needs a number, got {:?}", key_name, value));
+    }
+    if key == Key::Device {
+        // device names are long; match any part of them
+        let re = RegexBuilder::new(&regex::escape(value))
+            .case_insensitive(true)
+            .build()
+            .map_err(|e| e.to_string())?;
+        return Ok(Test::Search(key, re));
+    }
+    Ok(Test::Equals(key, glob_to_regex(value)))
+}
+
+fn field_values(key: Key, s: &Stream, scope: &Scope) -> Vec<String> {
+    match key {
+        Key::Id => vec![s.id.to_string()],
+        Key::Name => vec![s.name.clone()],
+        Key::Binary => s.binary.iter().cloned().collect(),
+        Key::Role => s.role.iter().cloned().collect(),
+        Key::Media => s.media_name.iter().cloned().collect(),
+        Key::Flatpak => s.app_id.iter().cloned().collect(),
+        Key::Pid => s.pid.iter().map(u32::to_string).collect(),
+        Key::Device => scope
+            .devices
+            .iter()
+            .filter(|d| Some(d.id) == s.device_id)
+            .flat_map(|d| [d.name.clone(), d.description.clone()])
+            .collect(),
+        Key::FocusedRole => match &scope.focused {
+            Some(window) if window.owns(s) => s.role.iter().cloned().collect(),
+            _ => Vec::new(),
+        },
+    }
+}
+
+fn eval(test: &Test, s: &Stream, scope: &Scope) -> bool {
+    match test {
+        Test::All => true,
+        Test::Equals(key, re) | Test::Search(key, re) => {
+            field_values(*key, s, scope).iter().any(|v| re.is_match(v))
+        }
+    }
+}
@@ -0,0 +1,493 @@
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::process::Command;
+use std::rc::Rc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use gtk4::gio::{self, BusNameOwnerFlags, BusNameWatcherFlags, BusType, Cancellable, DBusCallFlags, DBusConnection, DBusNodeInfo};
+use gtk4::glib::{self, variant::ObjectPath, ControlFlow, Variant, VariantTy};
+use gtk4::prelude::*;
+
+use crate::audio::{drain_events, AudioBackend, AudioError, Device, Stream};
+use crate::bar::default_device;
+use crate::config::Config;
+use crate::osd::level_icon;
+use crate::tr;
+use crate::values::format_percent;
+
+const ITEM_PATH: &str = "/StatusNotifierItem";
+const MENU_PATH: &str = "/MenuBar";
+const ITEM_INTERFACE: &str = "org.kde.StatusNotifierItem";
+const MENU_INTERFACE: &str = "com.canonical.dbusmenu";
+const WATCHER: &str = "org.kde.StatusNotifierWatcher";
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.kde.StatusNotifierItem">
+    <property name="Category" type="s" access="read"/>
+    <property name="Id" type="s" access="read"/>
+    <property name="Title" type="s" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="IconName" type="s" access="read"/>
+    <property name="ToolTip" type="(sa(iiay)ss)" access="read"/>
+    <property name="ItemIsMenu" type="b" access="read"/>
+    <property name="Menu" type="o" access="read"/>
+    <method name="Activate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="SecondaryActivate">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="ContextMenu">
+      <arg name="x" type="i" direction="in"/>
+      <arg name="y" type="i" direction="in"/>
+    </method>
+    <method name="Scroll">
+      <arg name="delta" type="i" direction="in"/>
+      <arg name="orientation" type="s" direction="in"/>
+    </method>
+    <signal name="NewIcon"/>
+    <signal name="NewToolTip"/>
+  </interface>
+  <interface name="com.canonical.dbusmenu">
+    <property name="Version" type="u" access="read"/>
+    <property name="Status" type="s" access="read"/>
+    <property name="TextDirection" type="s" access="read"/>
+    <method name="GetLayout">
+      <arg name="parentId" type="i" direction="in"/>
+      <arg name="recursionDepth" type="i" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="revision" type="u" direction="out"/>
+      <arg name="layout" type="(ia{sv}av)" direction="out"/>
+    </method>
+    <method name="GetGroupProperties">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="propertyNames" type="as" direction="in"/>
+      <arg name="properties" type="a(ia{sv})" direction="out"/>
+    </method>
+    <method name="Event">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="eventId" type="s" direction="in"/>
+      <arg name="data" type="v" direction="in"/>
+      <arg name="timestamp" type="u" direction="in"/>
+    </method>
+    <method name="EventGroup">
+      <arg name="events" type="a(isvu)" direction="in"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <method name="AboutToShow">
+      <arg name="id" type="i" direction="in"/>
+      <arg name="needUpdate" type="b" direction="out"/>
+    </method>
+    <method name="AboutToShowGroup">
+      <arg name="ids" type="ai" direction="in"/>
+      <arg name="updatesNeeded" type="ai" direction="out"/>
+      <arg name="idErrors" type="ai" direction="out"/>
+    </method>
+    <signal name="LayoutUpdated">
+      <arg name="revision" type="u"/>
+      <arg name="parent" type="i"/>
+    </signal>
+  </interface>
+</node>"#;
+
+// Menu item ids; stream items are offset by their stream id.
+const ROOT_ID: i32 = 0;
+const OPEN_ID: i32 = 1;
+const MUTE_OUTPUT_ID: i32 = 2;
+const SEPARATOR_ID: i32 = 3;
+const STREAM_ID_BASE: i32 = 1000;
+
+const PUMP_INTERVAL: Duration = Duration::from_millis(50);
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+// One wheel notch, in the units most hosts send.
+const SCROLL_NOTCH: i32 = 120;
+
+/// What the tray shows, refreshed from the backend.
+struct Tray {
+    backend: Box<dyn AudioBackend>,
+    device: Option<Device>,
+    streams: Vec<Stream>,
+    revision: u32,
+    /// Volume change per wheel notch, the config's `scroll_step`.
+    scroll_step: f32,
+}
+
+/// What changed in a refresh.
+struct Changes {
+    icon: bool,
+    menu: bool,
+}
+
+fn props(pairs: &[(&str, Variant)]) -> HashMap<String, Variant> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+}
+
+// Menu ids of the streams; an id too large for one has no item.
+fn menu_id(s: &Stream) -> Option<i32> {
+    i32::try_from(s.id).ok().and_then(|id| id.checked_add(STREAM_ID_BASE))
+}
+
+fn menu_node(id: i32, props: HashMap<String, Variant>, children: &[Variant]) -> Variant {
+    Variant::tuple_from_iter([
+        id.to_variant(),
+        props.to_variant(),
+        Variant::array_from_iter_with_type(VariantTy::VARIANT, children.iter().map(Variant::from_variant)),
+    ])
+}
+
+impl Tray {
+    fn new(backend: Box<dyn AudioBackend>) -> Self {
+        let scroll_step = Config::load_default().scroll_step as f32;
+        Tray { backend, device: None, streams: Vec::new(), revision: 1, scroll_step }
+    }
+
+    fn refresh(&mut self) -> Result<Changes, AudioError> {
+        let devices = match self.backend.list_devices() {
+            Err(AudioError::NotAvailable) => Vec::new(),
+            other => other?,
+        };
+        let device = default_device(&devices).cloned();
+        let streams = self.backend.list_streams()?;
+
+        let changes = Changes { icon: device != self.device, menu: device != self.device || streams != self.streams };
+        if changes.menu {
+            self.revision += 1;
+        }
+        self.device = device;
+        self.streams = streams;
+        Ok(changes)
+    }
+
+    fn icon_name(&self) -> &'static str {
+        self.device.as_ref().map_or("audio-volume-muted", |d| level_icon(d.volume_01, d.mute))
+    }
+
+    fn tooltip(&self) -> Variant {
+        let (title, description) = match &self.device {
+            Some(d) if d.mute => (tr!("Muted"), d.description.clone()),
+            Some(d) => (tr!("Volume {}", format_percent(d.volume_01)), d.description.clone()),
+            None => (tr!("No output"), String::new()),
+        };
+        (self.icon_name(), Vec::<(i32, i32, Vec<u8>)>::new(), title, description).to_variant()
+    }
+
+    fn property(&self, interface: &str, name: &str) -> Variant {
+        match (interface, name) {
+            (MENU_INTERFACE, "Version") => 3u32.to_variant(),
+            (MENU_INTERFACE, "TextDirection") => "ltr".to_variant(),
+            (MENU_INTERFACE, _) => "normal".to_variant(),
+            (_, name) => self.item_property(name),
+        }
+    }
+
+    fn item_property(&self, name: &str) -> Variant {
+        match name {
+            "Category" => "Hardware".to_variant(),
+            "Id" => "wlvolctl".to_variant(),
+            "Title" => tr!("Volume").to_variant(),
+            "IconName" => self.icon_name().to_variant(),
+            "ToolTip" => self.tooltip(),
+            "ItemIsMenu" => false.to_variant(),
+            "Menu" => ObjectPath::try_from(MENU_PATH).unwrap().to_variant(),
+            // Status; GDBus rejects names missing from the introspection data
+            _ => "Active".to_variant(),
+        }
+    }
+
+    fn item_props(&self, id: i32) -> Option<HashMap<String, Variant>> {
+        let checkmark = |label: String, checked: bool| {
+            props(&[
+                ("label", label.to_variant()),
+                ("toggle-type", "checkmark".to_variant()),
+                ("toggle-state", (checked as i32).to_variant()),
+            ])
+        };
+        match id {
+            ROOT_ID => Some(props(&[("children-display", "submenu".to_variant())])),
+            OPEN_ID => Some(props(&[("label", tr!("Open Mixer").to_variant())])),
+            MUTE_OUTPUT_ID => {
+                let d = self.device.as_ref()?;
+                Some(checkmark(tr!("Mute {}", d.description), d.mute))
+            }
+            SEPARATOR_ID => Some(props(&[("type", "separator".to_variant())])),
+            _ => {
+                let s = self.streams.iter().find(|s| menu_id(s) == Some(id))?;
+                Some(checkmark(tr!("Mute {} ({})", s.name, format_percent(s.volume_01)), s.mute))
+            }
+        }
+    }
+
+    fn item_ids(&self) -> Vec<i32> {
+        let mut ids = vec![OPEN_ID];
+        if self.device.is_some() {
+            ids.push(MUTE_OUTPUT_ID);
+        }
+        if !self.streams.is_empty() {
+            ids.push(SEPARATOR_ID);
+            ids.extend(self.streams.iter().filter_map(menu_id));
+        }
+        ids
+    }
+
+    fn layout(&self) -> Variant {
+        let children: Vec<Variant> = self
+            .item_ids()
+            .into_iter()
+            .filter_map(|id| Some(menu_node(id, self.item_props(id)?, &[])))
+            .collect();
+        menu_node(ROOT_ID, self.item_props(ROOT_ID).unwrap_or_default(), &children)
+    }
+
+    fn clicked(&self, id: i32) -> Result<(), AudioError> {
+        match id {
+            OPEN_ID => open_popup(),
+            MUTE_OUTPUT_ID => {
+                if let Some(d) = &self.device {
+                    self.backend.set_device_mute(d.id, !d.mute)?;
+                }
+            }
+            _ => {
+                if let Some(s) = self.streams.iter().find(|s| menu_id(s) == Some(id)) {
+                    self.backend.set_mute(s.id, !s.mute)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn scroll(&self, delta: i32) -> Result<(), AudioError> {
+        let Some(d) = &self.device else { return Ok(()) };
+        let notches = if delta.abs() >= SCROLL_NOTCH { delta / SCROLL_NOTCH } else { delta.signum() };
+        let vol = (d.volume_01 + notches as f32 * self.scroll_step).clamp(0.0, 1.0);
+        self.backend.set_device_volume(d.id, vol)
+    }
+}
+
+fn open_popup() {
+    let spawned = std::env::current_exe().and_then(|exe| Command::new(exe).args(["--popup", "--show"]).spawn());
+    match spawned {
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+        Err(e) => log::warn!("tray: cannot start the popup: {}", e),
+    }
+}
+
+fn emit(conn: &DBusConnection, path: &str, interface: &str, signal: &str, params: Option<Variant>) {
+    if let Err(e) = conn.emit_signal(None, path, interface, signal, params.as_ref()) {
+        log::warn!("tray: failed to emit {}: {}", signal, e);
+    }
+}
+
+fn refresh_and_emit(conn: &DBusConnection, tray: &RefCell<Tray>) {
+    let mut tray = tray.borrow_mut();
+    match tray.refresh() {
+        Ok(changes) => {
+            if changes.icon {
+                emit(conn, ITEM_PATH, ITEM_INTERFACE, "NewIcon", None);
+                emit(conn, ITEM_PATH, ITEM_INTERFACE, "NewToolTip", None);
+            }
+            if changes.menu {
+                emit(conn, MENU_PATH, MENU_INTERFACE, "LayoutUpdated", Some((tray.revision, ROOT_ID).to_variant()));
+            }
+        }
+        Err(e) => log::warn!("tray: {}", e),
+    }
+}
+
+fn item_call(tray: &RefCell<Tray>, method: &str, params: &Variant) -> Result<Option<Variant>, AudioError> {
+    let tray = tray.borrow();
+    match method {
+        "Activate" => open_popup(),
+        "SecondaryActivate" => tray.clicked(MUTE_OUTPUT_ID)?,
+        "Scroll" => {
+            if let Some((delta, orientation)) = params.get::<(i32, String)>()
+                && orientation.eq_ignore_ascii_case("vertical")
+            {
+                tray.scroll(delta)?;
+            }
+        }
+        // ItemIsMenu is false, so hosts show the menu themselves
+        _ => {}
+    }
+    Ok(None)
+}
+
+fn menu_call(tray: &RefCell<Tray>, method: &str, params: &Variant) -> Result<Option<Variant>, AudioError> {
+    let tray = tray.borrow();
+    let reply = match method {
+        "GetLayout" => Variant::tuple_from_iter([tray.revision.to_variant(), tray.layout()]),
+        "GetGroupProperties" => {
+            let (ids, _names) = params.get::<(Vec<i32>, Vec<String>)>().unwrap_or_default();
+            let found: Vec<(i32, HashMap<String, Variant>)> =
+                ids.into_iter().filter_map(|id| Some((id, tray.item_props(id)?))).collect();
+            (found,).to_variant()
+        }
+        "Event" => {
+            if let Some((id, event, _, _)) = params.get::<(i32, String, Variant, u32)>()
+                && event == "clicked"
+            {
+                tray.clicked(id)?;
+            }
+            return Ok(None);
+        }
+        "EventGroup" => {
+            let events = params.get::<(Vec<(i32, String, Variant, u32)>,)>().map(|(e,)| e).unwrap_or_default();
+            for (id, event, _, _) in events {
+                if event == "clicked" {
+                    tray.clicked(id)?;
+                }
+            }
+            (Vec::<i32>::new(),).to_variant()
+        }
+        "AboutToShow" => (false,).to_variant(),
+        "AboutToShowGroup" => (Vec::<i32>::new(), Vec::<i32>::new()).to_variant(),
+        _ => return Ok(None),
+    };
+    Ok(Some(reply))
+}
+
+type Handler = fn(&RefCell<Tray>, &str, &Variant) -> Result<Option<Variant>, AudioError>;
+
+fn register(conn: &DBusConnection, tray: &Rc<RefCell<Tray>>) -> Result<(), glib::Error> {
+    let node = DBusNodeInfo::for_xml(INTROSPECTION)?;
+    let objects: [(&str, &str, Handler); 2] =
+        [(ITEM_PATH, ITEM_INTERFACE, item_call), (MENU_PATH, MENU_INTERFACE, menu_call)];
+
+    for (path, interface, handler) in objects {
+        let iface = node.lookup_interface(interface).expect("interface in introspection data");
+        let calls = tray.clone();
+        let props = tray.clone();
+        conn.register_object(
+            path,
+            &iface,
+            move |conn, _, _, _, method, params, invocation| {
+                let result = handler(&calls, method, &params);
+                match result {
+                    Ok(reply) => {
+                        invocation.return_value(reply.as_ref());
+                        if !matches!(method, "GetLayout" | "GetGroupProperties" | "AboutToShow" | "AboutToShowGroup") {
+                            refresh_and_emit(&conn, &calls);
+                        }
+                    }
+                    Err(e) => invocation.return_dbus_error("org.wlvolctl.Tray.Error.Failed", &e.to_string()),
+                }
+            },
+            move |_, _, _, interface, name| props.borrow().property(interface, name),
+            |_, _, _, _, _, _| false,
+        )?;
+    }
+    Ok(())
+}
+
+fn register_with_watcher(conn: &DBusConnection, service: &str) {
+    let result = conn.call_sync(
+        Some(WATCHER),
+        "/StatusNotifierWatcher",
+        WATCHER,
+        "RegisterStatusNotifierItem",
+        Some(&(service,).to_variant()),
+        None,
+        DBusCallFlags::NONE,
+        -1,
+        Cancellable::NONE,
+    );
+    match result {
+        Ok(_) => log::info!("tray: registered {}", service),
+        Err(e) => log::warn!("tray: cannot register with {}: {}", WATCHER, e),
+    }
+}
+
+fn start_event_pump(conn: DBusConnection, tray: Rc<RefCell<Tray>>) {
+    let mut events = match tray.borrow().backend.subscribe() {
+        Ok(rx) => Some(rx),
+        Err(e) => {
+            log::warn!("tray: no server events ({}), polling instead", e);
+            None
+        }
+    };
+    let mut last_refresh = Instant::now();
+
+    glib::timeout_add_local(PUMP_INTERVAL, move || {
+        let due = match &events {
+            Some(rx) => drain_events(rx).unwrap_or_else(|| {
+                events = None;
+                true
+            }),
+            None => last_refresh.elapsed() >= POLL_INTERVAL,
+        };
+        if due {
+            refresh_and_emit(&conn, &tray);
+            last_refresh = Instant::now();
+        }
+        ControlFlow::Continue
+    });
+}
+
+/// Runs `wlvolctl tray`, a StatusNotifierItem for the default output with
+/// a menu of the app streams. Returns the exit code.
+pub fn run_tray(backend: Box<dyn AudioBackend>) -> i32 {
+    let main_loop = glib::MainLoop::new(None, false);
+    let tray = Rc::new(RefCell::new(Tray::new(backend)));
+    if let Err(e) = tray.borrow_mut().refresh() {
+        log::warn!("tray: {}", e);
+    }
+
+    let service = format!("org.kde.StatusNotifierItem-{}-1", std::process::id());
+    let exit_code = Rc::new(Cell::new(0));
+    let watcher = Rc::new(RefCell::new(None));
+    let owner = gio::bus_own_name(
+        BusType::Session,
+        &service,
+        BusNameOwnerFlags::NONE,
+        {
+            let tray = tray.clone();
+            let main_loop = main_loop.clone();
+            let exit_code = exit_code.clone();
+            move |conn, _| {
+                if let Err(e) = register(&conn, &tray) {
+                    eprintln!("wlvolctl: {}", tr!("cannot export the tray item: {}", e));
+                    exit_code.set(1);
+                    main_loop.quit();
+                    return;
+                }
+                start_event_pump(conn, tray.clone());
+            }
+        },
+        {
+            let watcher = watcher.clone();
+            // (Re-)register whenever a watcher shows up, e.g. after a bar restart.
+            move |conn, name| {
+                let service = name.to_string();
+                let id = gio::bus_watch_name_on_connection(
+                    &conn,
+                    WATCHER,
+                    BusNameWatcherFlags::NONE,
+                    move |conn, _, _| register_with_watcher(&conn, &service),
+                    |_, _| log::info!("tray: no {} running", WATCHER),
+                );
+                watcher.replace(Some(id));
+            }
+        },
+        {
+            let main_loop = main_loop.clone();
+            let exit_code = exit_code.clone();
+            move |_, name| {
+                eprintln!("wlvolctl: {}", tr!("cannot own {} on the session bus", name));
+                exit_code.set(1);
+                main_loop.quit();
+            }
+        },
+    );
+
+    main_loop.run();
+    if let Some(id) = watcher.take() {
+        gio::bus_unwatch_name(id);
+    }
+    gio::bus_unown_name(owner);
+    exit_code.get()
+}
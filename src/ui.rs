@@ -1,403 +1,1180 @@
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
-use std::fs;
-use std::sync::{Arc, Mutex};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::mpsc::Receiver;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use shellexpand;
-
-use ini::Ini;
-
-use gtk4::gdk::Key;
+use gtk4::gdk::{ContentProvider, Display, DragAction, EventType, ModifierType};
+use gtk4::gio::{self, ApplicationFlags, FileMonitor};
 use gtk4::gdk_pixbuf::Pixbuf;
-use gtk4::glib::{timeout_add_local, ControlFlow};
+use gtk4::pango::EllipsizeMode;
+use gtk4::glib::{
+    self as glib, timeout_add_local, Char, ControlFlow, OptionArg, OptionFlags, Propagation, SignalHandlerId, SourceId,
+};
 use gtk4::prelude::*;
 use gtk4::{
-    Application, ApplicationWindow, Box as GtkBox, EventControllerFocus, EventControllerKey, Image,
-    Label, Orientation, Scale, Separator, ToggleButton, Window,
+    Application, ApplicationWindow, Box as GtkBox, Button, CssProvider, DragSource, DropDown, DropTarget, Editable,
+    Entry, EventControllerFocus, EventControllerKey, EventControllerLegacy, EventControllerScroll,
+    EventControllerScrollFlags, EventSequenceState, Expander, Expression, GestureClick, Image, Label, Orientation,
+    PropagationPhase, Scale, Separator, StringList, StringObject, ToggleButton, Widget, WidgetPaintable, Window,
 };
 
-use crate::audio::{AudioBackend, Stream};
+use crate::audio::Stream;
+use crate::config::{self, app_key, format_app_list, Config, Theme};
+use crate::groups::{group_streams, AppIdentity, StreamGroup};
+use crate::cache::{self, LookupCache};
+use crate::desktop::{user_locale_keys, AppIndex};
+use crate::icons::IconResolver;
+use crate::keys::Action;
+use crate::limits::{CappedBackend, Limits};
+use crate::lock::{self, LockingBackend};
 use crate::pulseaudio_cli::PulseAudioCli;
+use crate::rules::{RuleEngine, RuleSet};
+use crate::scenes::{Scene, SceneStore};
+use crate::watch::watch_file;
+use crate::worker::{BackendWorker, Reply, Request};
+use crate::writes::WriteCoordinator;
+use crate::{tr, trn};
+
+// Caps sit outside the lock so a locked value is always within its cap.
+type UiBackend = CappedBackend<LockingBackend<PulseAudioCli>>;
+
+const SCENE_FADE: Duration = Duration::from_millis(800);
+
+// How often replies from the backend worker are picked up.
+const PUMP_INTERVAL: Duration = Duration::from_millis(50);
+
+// A moving slider writes a stream at most this often...
+const WRITE_INTERVAL: Duration = Duration::from_millis(100);
+// ...and the server's view of it is ignored until this long after.
+const WRITE_SETTLE: Duration = Duration::from_millis(500);
+
+// The column holding the focus is the one the keys act on.
+const COLUMN_CSS: &str = "
+.stream-column { padding: 4px; border-radius: 6px; }
+.stream-column:focus-within { box-shadow: inset 0 0 0 2px @theme_selected_bg_color; }
+";
 
+const POPUP_APP_ID: &str = "com.example.wlvolctl.popup";
+
+/// What a `--popup` invocation asks of the popup.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PopupRequest {
+    Toggle,
+    Show,
+    Hide,
+}
+
+/// Runs the popup, or forwards the request to the instance already running
+/// it: GApplication uniqueness sends our command line to that instance.
 pub fn run_popup_ui() {
     // Strip --popup so GTK doesn't see unknown args
     let mut gtk_args: Vec<String> = std::env::args().collect();
     gtk_args.retain(|a| a != "--popup");
+    if gtk_args.iter().any(|a| a == "--show") && gtk_args.iter().any(|a| a == "--hide") {
+        eprintln!("wlvolctl: {}", tr!("--show and --hide are exclusive"));
+        std::process::exit(2);
+    }
 
-    let app = Application::new(Some("com.example.wlvolctl.popup"), Default::default());
+    let app = Application::new(Some(POPUP_APP_ID), ApplicationFlags::HANDLES_COMMAND_LINE);
+    app.add_main_option("show", Char::from(0), OptionFlags::NONE, OptionArg::None, &tr!("Open the popup"), None);
+    app.add_main_option("hide", Char::from(0), OptionFlags::NONE, OptionArg::None, &tr!("Close the popup"), None);
 
-    app.connect_activate(|app| {
-        // Invisible transient parent to allow proper modality/focus
-        let parent = ApplicationWindow::new(app);
-        parent.hide();
-
-        let popup = Window::builder()
-            .transient_for(&parent)
-            .decorated(false)
-            .resizable(false)
-            .build();
-
-        let backend: Arc<Mutex<PulseAudioCli>> = Arc::new(Mutex::new(PulseAudioCli));
-        let icon_cache = Arc::new(load_icon_cache());
-
-        let hbox = GtkBox::new(Orientation::Horizontal, 12);
-        popup.set_child(Some(&hbox));
-
-        // Refresh closure
-        let hbox_clone = hbox.clone();
-        let backend_clone = Arc::clone(&backend);
-        let icons_clone = Arc::clone(&icon_cache);
-
-        let update_ui = move || {
-            let streams: Vec<Stream> = {
-                let b = backend_clone.lock().unwrap();
-                b.list_streams().unwrap_or_default()
-            };
-
-            // Clear existing children (GTK4: iterate via first_child/next_sibling)
-            while let Some(child) = hbox_clone.first_child() {
-                hbox_clone.remove(&child);
-            }
+    let current: Rc<RefCell<Option<Window>>> = Rc::new(RefCell::new(None));
+    app.connect_command_line(move |app, cmdline| {
+        let options = cmdline.options_dict();
+        let request = if options.contains("show") {
+            PopupRequest::Show
+        } else if options.contains("hide") {
+            PopupRequest::Hide
+        } else {
+            PopupRequest::Toggle
+        };
 
-            if streams.is_empty() {
-                let empty = Label::new(Some("No active streams"));
-                hbox_clone.append(&empty);
-            } else {
-                for s in &streams {
-                    let col = build_column(&backend_clone, &icons_clone, s.clone());
-                    hbox_clone.append(&col);
-                }
+        let open = current.borrow().clone();
+        match (request, open) {
+            (PopupRequest::Show, Some(popup)) => popup.present(),
+            (PopupRequest::Hide | PopupRequest::Toggle, Some(popup)) => popup.close(),
+            (PopupRequest::Show | PopupRequest::Toggle, None) => {
+                *current.borrow_mut() = Some(build_popup(app, &current));
             }
+            (PopupRequest::Hide, None) => {}
+        }
+        0
+    });
 
-            hbox_clone.show();
-            ControlFlow::Continue
-        };
+    app.run_with_args(&gtk_args);
+}
 
-        // Run once immediately, then every 4 seconds
-        update_ui();
-        timeout_add_local(Duration::from_secs(4), update_ui);
+// The instance exits once the popup is closed.
+fn build_popup(app: &Application, current: &Rc<RefCell<Option<Window>>>) -> Window {
+    // Invisible transient parent to allow proper modality/focus
+    let parent = ApplicationWindow::new(app);
+    parent.hide();
 
-        // Auto-close on focus loss (GTK4 controllers, no Inhibit)
-        #[cfg(not(debug_assertions))]
-        {
-            let focus = EventControllerFocus::new();
-            let opened_at = Instant::now();
-            let popup_clone = popup.clone();
-
-            focus.connect_leave(move |_| {
-                let min_open = Duration::from_millis(2000); // adjust as needed
-                let elapsed = opened_at.elapsed();
-
-                if elapsed >= min_open {
-                    popup_clone.close();
-                } else {
-                    // Schedule closure once the minimum time has passed
-                    let remaining = min_open - elapsed;
-                    let popup_delayed = popup_clone.clone();
-                    glib::timeout_add_local_once(remaining, move || {
-                        popup_delayed.close();
-                    });
-                }
-            });
+    let popup = Window::builder()
+        .transient_for(&parent)
+        .decorated(false)
+        .resizable(false)
+        .build();
 
-            popup.add_controller(focus);
-        }
+    let hbox = GtkBox::new(Orientation::Horizontal, 12);
+    popup.set_child(Some(&hbox));
+    let view = StreamView::start(hbox);
 
-        // Close on Esc key
-        let key = EventControllerKey::new();
-        {
-            let popup = popup.clone();
-            key.connect_key_pressed(move |_, keyval, _, _| {
-                if keyval == Key::Escape {
-                    popup.close();
-                    true.into()
-                } else {
-                    false.into()
-                }
-            });
-        }
-        popup.add_controller(key);
+    // Auto-close on focus loss (GTK4 controllers, no Inhibit)
+    #[cfg(not(debug_assertions))]
+    {
+        let focus = EventControllerFocus::new();
+        let opened_at = Instant::now();
+        let popup_clone = popup.clone();
+        let view = view.clone();
 
-        popup.present();
-    });
+        focus.connect_leave(move |_| {
+            let min_open = view.config.borrow().popup_min_open;
+            let elapsed = opened_at.elapsed();
 
-    app.run_with_args(&gtk_args);
+            if elapsed >= min_open {
+                popup_clone.close();
+            } else {
+                // Schedule closure once the minimum time has passed
+                let remaining = min_open - elapsed;
+                let popup_delayed = popup_clone.clone();
+                glib::timeout_add_local_once(remaining, move || {
+                    popup_delayed.close();
+                });
+            }
+        });
+
+        popup.add_controller(focus);
+    }
+
+    add_key_bindings(&popup, &view, true);
+
+    {
+        let current = current.clone();
+        popup.connect_close_request(move |_| {
+            view.stop();
+            current.borrow_mut().take();
+            parent.close();
+            Propagation::Proceed
+        });
+    }
+
+    popup.present();
+    popup
 }
 
 pub fn run_full_ui() {
     let app = Application::new(Some("org.wlvolctl.ui"), Default::default());
     app.connect_activate(|app| {
-        let backend = Arc::new(Mutex::new(PulseAudioCli));
-        let icon_cache = Arc::new(load_icon_cache());
-
         let window = ApplicationWindow::new(app);
-        window.set_title(Some("wlvolctl POC"));
+        window.set_title(Some(&tr!("wlvolctl POC")));
         window.set_default_size(600, 300);
 
         let vbox = GtkBox::new(Orientation::Vertical, 6);
         window.set_child(Some(&vbox));
 
-        let header = Label::new(Some("Per-application volumes"));
+        let header = Label::new(Some(&tr!("Per-application volumes")));
         vbox.append(&header);
-        vbox.append(&Separator::new(Orientation::Horizontal));
 
         // Horizontal container for stream columns
         let streams_box = GtkBox::new(Orientation::Horizontal, 12);
+        let view = StreamView::start(streams_box.clone());
+        vbox.append(&build_scene_bar(&view));
+        vbox.append(&Separator::new(Orientation::Horizontal));
         vbox.append(&streams_box);
+        add_key_bindings(window.upcast_ref(), &view, false);
+
+        window.show();
+    });
+
+    app.run();
+}
+
+// Every volume change from the UI goes through the configured caps.
+fn new_backend() -> UiBackend {
+    let locking = LockingBackend::with_log(PulseAudioCli, lock::default_log_path());
+    CappedBackend::new(locking, Limits::load_default())
+}
+
+fn load_rules() -> RuleSet {
+    let path = RuleSet::default_path();
+    RuleSet::load(&path).unwrap_or_else(|e| {
+        eprintln!("{}", tr!("Ignoring rules in {}: {}", path.display(), e));
+        RuleSet::default()
+    })
+}
+
+// Written to the config file; its monitor then rebuilds the view. The new
+// list is made from the file as it is now, not from the config a column was
+// built with, which an earlier click may have changed since.
+fn save_app_list(key: &str, apps: impl FnOnce(&Config) -> Vec<String>) {
+    let path = Config::default_path();
+    let result = Config::load(&path)
+        .and_then(|current| config::update_file(&path, "apps", key, &format_app_list(&apps(&current))));
+    if let Err(e) = result {
+        eprintln!("{}", tr!("Failed to save {} in {}: {}", key, path.display(), e));
+    }
+}
+
+// Dropping a column's icon onto another column moves that app there.
+fn add_drop_target(col: &GtkBox, app: &str, shown: &Rc<RefCell<Vec<String>>>) {
+    let target = DropTarget::new(glib::Type::STRING, DragAction::MOVE);
+    let app = app.to_string();
+    let shown = Rc::clone(shown);
+    target.connect_drop(move |_, value, _, _| {
+        let Ok(dragged) = value.get::<String>() else { return false };
+        if dragged != app {
+            save_app_list("order", |config| config.moved(&shown.borrow(), &dragged, &app));
+        }
+        true
+    });
+    col.add_controller(target);
+}
 
-        // Refresh loop
-        let streams_box_clone = streams_box.clone();
-        let backend_clone = Arc::clone(&backend);
-        let icons_clone = Arc::clone(&icon_cache);
-
-        let update_ui = move || {
-            let streams = backend_clone
-                .lock()
-                .unwrap()
-                .list_streams()
-                .unwrap_or_default();
-
-            // Clear existing children
-            while let Some(child) = streams_box_clone.first_child() {
-                streams_box_clone.remove(&child);
+// Keyboard control of the columns, see `KeyBindings`. Keys are seen before
+// the focused widget gets them, so sliders move by the configured steps,
+// but never while typing into a text field. Only a popup closes.
+fn add_key_bindings(window: &Window, view: &Rc<StreamView>, closes: bool) {
+    let key = EventControllerKey::new();
+    key.set_propagation_phase(PropagationPhase::Capture);
+    let window1 = window.clone();
+    let view = Rc::clone(view);
+    key.connect_key_pressed(move |_, keyval, _, state| {
+        let focus = GtkWindowExt::focus(&window1);
+        if focus.as_ref().is_some_and(|f| f.is::<Editable>()) {
+            return Propagation::Proceed;
+        }
+        let Some(name) = keyval.name() else { return Propagation::Proceed };
+        let ctrl = state.contains(ModifierType::CONTROL_MASK);
+        let alt = state.contains(ModifierType::ALT_MASK);
+        let action = view.config.borrow().keys.action_for(&name, ctrl, alt);
+        let handled = match action {
+            Some(Action::Close) if closes => {
+                window1.close();
+                true
             }
+            Some(Action::Close) | None => false,
+            Some(action) => view.act(action, focus.as_ref()),
+        };
+        if !handled {
+            return Propagation::Proceed;
+        }
+        window1.set_focus_visible(true);
+        Propagation::Stop
+    });
+    window.add_controller(key);
+}
 
-            if streams.is_empty() {
-                let empty = Label::new(Some("No active streams"));
-                streams_box_clone.append(&empty);
-            } else {
-                for s in &streams {
-                    let col = build_column(&backend_clone, &icons_clone, s.clone());
-                    streams_box_clone.append(&col);
+fn add_column_style() {
+    let Some(display) = Display::default() else { return };
+    let provider = CssProvider::new();
+    provider.load_from_data(COLUMN_CSS);
+    gtk4::style_context_add_provider_for_display(&display, &provider, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION);
+}
+
+fn apply_theme(theme: Theme) {
+    let Some(settings) = gtk4::Settings::default() else { return };
+    match theme {
+        Theme::System => settings.reset_property("gtk-application-prefer-dark-theme"),
+        Theme::Light => settings.set_gtk_application_prefer_dark_theme(false),
+        Theme::Dark => settings.set_gtk_application_prefer_dark_theme(true),
+    }
+}
+
+// The backend as the widgets see it: calls go through the worker thread,
+// slider writes paced by the coordinator, while the lock state lives in
+// memory and is read directly.
+#[derive(Clone)]
+struct BackendHandle {
+    worker: Rc<BackendWorker>,
+    writes: Rc<RefCell<WriteCoordinator>>,
+    shared: Arc<UiBackend>,
+}
+
+impl BackendHandle {
+    // A volume the user chose; sent now, or by `flush` once the stream's
+    // write interval has passed.
+    fn set_volume(&self, stream_id: u32, vol_01: f32) {
+        if let Some(vol) = self.writes.borrow_mut().user_set(stream_id, vol_01, Instant::now()) {
+            self.worker.send(Request::SetVolume(stream_id, vol));
+        }
+    }
+
+    fn flush(&self) {
+        for (id, vol) in self.writes.borrow_mut().due(Instant::now()) {
+            self.worker.send(Request::SetVolume(id, vol));
+        }
+    }
+
+    // Whether what the server reports for these streams can be shown
+    // without undoing a write still on its way.
+    fn settled(&self, ids: &[u32]) -> bool {
+        let writes = self.writes.borrow();
+        let now = Instant::now();
+        ids.iter().all(|&id| writes.settled(id, now))
+    }
+}
+
+/// One column per app with visible streams, refreshed on a timer and
+/// whenever the rules file changes. Listing runs on the backend worker;
+/// its replies are picked up on the main loop. Refreshes add and remove
+/// columns as apps come and go and update the others in place; a config
+/// change or newly loaded icons rebuild them all. An invalid edit is
+/// reported and the previous settings stay in effect.
+struct StreamView {
+    container: GtkBox,
+    empty: Label,
+    columns: RefCell<HashMap<AppIdentity, Column>>,
+    /// Apps in the order shown, for dropping a column onto another.
+    shown: Rc<RefCell<Vec<String>>>,
+    backend: BackendHandle,
+    replies: Receiver<Reply>,
+    /// The last listing, without the streams the rules hide.
+    streams: RefCell<Vec<Stream>>,
+    config: RefCell<Config>,
+    /// None until loaded in the background.
+    icons: RefCell<Option<Rc<StreamIcons>>>,
+    timer: RefCell<Option<SourceId>>,
+    pump: RefCell<Option<SourceId>>,
+    monitors: RefCell<Vec<FileMonitor>>,
+}
+
+impl StreamView {
+    // The timers own the view; the file monitors only refer to it.
+    fn start(container: GtkBox) -> Rc<StreamView> {
+        let config = Config::load_default();
+        apply_theme(config.theme);
+        add_column_style();
+        let empty = Label::new(Some(&tr!("No active streams")));
+        container.append(&empty);
+        let shared = Arc::new(new_backend());
+        let (worker, replies) = BackendWorker::spawn(Arc::clone(&shared), RuleEngine::new(load_rules()));
+        let view = Rc::new(StreamView {
+            container,
+            empty,
+            columns: RefCell::new(HashMap::new()),
+            shown: Rc::new(RefCell::new(Vec::new())),
+            backend: BackendHandle {
+                worker: Rc::new(worker),
+                writes: Rc::new(RefCell::new(WriteCoordinator::new(WRITE_INTERVAL, WRITE_SETTLE))),
+                shared,
+            },
+            replies,
+            streams: RefCell::new(Vec::new()),
+            icons: RefCell::new(None),
+            config: RefCell::new(config),
+            timer: RefCell::new(None),
+            pump: RefCell::new(None),
+            monitors: RefCell::new(Vec::new()),
+        });
+
+        let weak = Rc::downgrade(&view);
+        let config_monitor = watch_file(&Config::default_path(), move || {
+            if let Some(view) = weak.upgrade() {
+                view.reload_config();
+            }
+        });
+        let weak = Rc::downgrade(&view);
+        let rules_monitor = watch_file(&RuleSet::default_path(), move || {
+            if let Some(view) = weak.upgrade() {
+                view.reload_rules();
+            }
+        });
+        let weak = Rc::downgrade(&view);
+        let limits_monitor = watch_file(&Limits::default_path(), move || {
+            if let Some(view) = weak.upgrade() {
+                view.reload_limits();
+            }
+        });
+        view.monitors.borrow_mut().extend(config_monitor.into_iter().chain(rules_monitor).chain(limits_monitor));
+
+        view.refresh();
+        view.start_pump();
+        view.restart_timer();
+        view.load_icons();
+        view
+    }
+
+    fn stop(&self) {
+        for source in [self.timer.take(), self.pump.take()].into_iter().flatten() {
+            source.remove();
+        }
+        self.monitors.borrow_mut().clear();
+    }
+
+    fn refresh(&self) {
+        self.backend.worker.send(Request::List);
+    }
+
+    // Sends held back slider values that are due and shows the newest
+    // listing the worker sent since the last look.
+    fn start_pump(self: &Rc<Self>) {
+        let view = Rc::clone(self);
+        let pump = timeout_add_local(PUMP_INTERVAL, move || {
+            view.backend.flush();
+            let mut newest = None;
+            for reply in view.replies.try_iter() {
+                match reply {
+                    Reply::Streams(streams) => newest = Some(streams),
+                    Reply::Failed { what, error } => log::warn!("ui: cannot {}: {}", what, error),
+                }
+            }
+            if let Some(streams) = newest {
+                *view.streams.borrow_mut() = streams;
+                view.update();
+            }
+            ControlFlow::Continue
+        });
+        *self.pump.borrow_mut() = Some(pump);
+    }
+
+    // Columns show a generic icon until their own ones are found.
+    fn load_icons(self: &Rc<Self>) {
+        let config = self.config.borrow().clone();
+        let theme_name = icon_theme_name();
+        let weak = Rc::downgrade(self);
+        glib::MainContext::default().spawn_local(async move {
+            match gio::spawn_blocking(move || StreamIcons::load(&config, theme_name)).await {
+                Ok(icons) => {
+                    if let Some(view) = weak.upgrade() {
+                        *view.icons.borrow_mut() = Some(Rc::new(icons));
+                        view.rebuild();
+                    }
                 }
+                Err(_) => eprintln!("{}", tr!("Failed to load icons")),
             }
+        });
+    }
 
-            streams_box_clone.show();
+    fn restart_timer(self: &Rc<Self>) {
+        let interval = self.config.borrow().refresh_interval;
+        let view = Rc::clone(self);
+        let timer = timeout_add_local(interval, move || {
+            view.refresh();
             ControlFlow::Continue
+        });
+        if let Some(old) = self.timer.replace(Some(timer)) {
+            old.remove();
+        }
+    }
+
+    // Shows the last listing as the config says: some apps hidden, some
+    // first.
+    fn update(&self) {
+        let config = self.config.borrow();
+        let mut streams: Vec<Stream> = self.streams.borrow().iter().filter(|s| !config.hides(s)).cloned().collect();
+        config.sort_streams(&mut streams);
+        drop(config);
+        let groups = group_streams(streams);
+        self.empty.set_visible(groups.is_empty());
+
+        let mut shown = self.shown.borrow_mut();
+        shown.clear();
+        for g in &groups {
+            if !shown.contains(&app_key(g.first())) {
+                shown.push(app_key(g.first()));
+            }
+        }
+        drop(shown);
+
+        let icons = self.icons.borrow().clone();
+        let config = self.config.borrow().clone();
+        let mut columns = self.columns.borrow_mut();
+        columns.retain(|identity, col| {
+            let gone = !groups.iter().any(|g| g.identity == *identity);
+            if gone {
+                self.container.remove(&col.root);
+            }
+            !gone
+        });
+        let mut previous: Option<GtkBox> = None;
+        for g in groups {
+            let identity = g.identity.clone();
+            if let Some(col) = columns.get(&identity) {
+                col.update(&self.backend, icons.as_deref(), &config, g);
+            } else {
+                let app = app_key(g.first());
+                let col = Column::new(&self.backend, icons.as_deref(), &config, g);
+                add_drop_target(&col.root, &app, &self.shown);
+                self.container.append(&col.root);
+                columns.insert(identity.clone(), col);
+            }
+            // in the order the config sorts apps in
+            let root = &columns[&identity].root;
+            self.container.reorder_child_after(root, previous.as_ref());
+            previous = Some(root.clone());
+        }
+
+        self.container.show();
+        if let Some(icons) = &*self.icons.borrow() {
+            icons.save();
+        }
+    }
+
+    fn reload_config(self: &Rc<Self>) {
+        let path = Config::default_path();
+        let new = match Config::load(&path) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("{}", tr!("Keeping the previous config, {} is invalid: {}", path.display(), e));
+                return;
+            }
         };
+        let old = self.config.replace(new.clone());
+        if old == new {
+            return;
+        }
 
-        update_ui();
-        timeout_add_local(Duration::from_secs(4), update_ui);
+        if old.theme != new.theme {
+            apply_theme(new.theme);
+        }
+        if old.desktop_dirs != new.desktop_dirs || old.flatpak_icon_dirs != new.flatpak_icon_dirs {
+            self.load_icons();
+        }
+        if old.refresh_interval != new.refresh_interval {
+            self.restart_timer();
+        }
+        self.rebuild();
+    }
 
-        window.show();
-    });
+    // Drops every column, for changes to how they are built.
+    fn rebuild(&self) {
+        for (_, col) in self.columns.borrow_mut().drain() {
+            self.container.remove(&col.root);
+        }
+        self.update();
+    }
 
-    app.run();
+    // Runs a key's action on the column holding the focus, or the first
+    // one. A focused member slider is the one volume keys move. Returns
+    // whether there was a column to act on.
+    fn act(&self, action: Action, focus: Option<&Widget>) -> bool {
+        let columns = self.columns.borrow();
+        let mut order = Vec::new();
+        let mut child = self.container.first_child();
+        while let Some(widget) = child {
+            order.extend(columns.values().find(|c| c.root == widget));
+            child = widget.next_sibling();
+        }
+        let current = focus.and_then(|f| order.iter().position(|c| f.is_ancestor(&c.root)));
+        let index = match action {
+            Action::Previous => current.map_or(0, |i| i.saturating_sub(1)),
+            Action::Next => current.map_or(0, |i| i + 1),
+            _ => current.unwrap_or(0),
+        };
+        let Some(col) = order.get(index).or(order.last()) else { return false };
+
+        let scale = focus
+            .and_then(|f| f.downcast_ref::<Scale>())
+            .filter(|s| s.is_ancestor(&col.root))
+            .unwrap_or(&col.scale);
+        let config = self.config.borrow();
+        let nudge = |step: f64| scale.set_value(scale.value() + step);
+        match action {
+            Action::Previous | Action::Next => {}
+            Action::VolumeUp => nudge(config.slider_step),
+            Action::VolumeDown => nudge(-config.slider_step),
+            Action::PageUp => nudge(config.page_step),
+            Action::PageDown => nudge(-config.page_step),
+            Action::ToggleMute => col.mute.set_active(!col.mute.is_active()),
+            Action::Jump(tenths) => scale.set_value(f64::from(tenths) / 10.0),
+            Action::Close => return false,
+        }
+        scale.grab_focus();
+        true
+    }
+
+    // The worker shares the backend, so the next listing applies them.
+    fn reload_limits(&self) {
+        let path = Limits::default_path();
+        match Limits::load(&path) {
+            Ok(limits) => {
+                self.backend.shared.set_limits(limits);
+                self.refresh();
+            }
+            Err(e) => eprintln!("{}", tr!("Keeping the previous limits, {} is invalid: {}", path.display(), e)),
+        }
+    }
+
+    fn reload_rules(&self) {
+        let path = RuleSet::default_path();
+        match RuleSet::load(&path) {
+            Ok(rules) => {
+                self.backend.worker.send(Request::SetRules(rules));
+                self.refresh();
+            }
+            Err(e) => eprintln!("{}", tr!("Keeping the previous rules, {} is invalid: {}", path.display(), e)),
+        }
+    }
 }
 
-fn load_icon_cache() -> HashMap<String, String> {
-    let mut map = HashMap::new();
-
-    // Desktop files
-    let desktop_dirs = vec!["/usr/share/applications", "~/.local/share/applications"];
-    for dir in desktop_dirs {
-        let expanded = shellexpand::tilde(dir).to_string();
-        if let Ok(entries) = std::fs::read_dir(&expanded) {
-            for entry in entries.flatten() {
-                if entry.path().extension().and_then(|s| s.to_str()) == Some("desktop") {
-                    if let Ok(conf) = Ini::load_from_file(entry.path()) {
-                        if let Some(section) = conf.section(Some("Desktop Entry")) {
-                            if let (Some(name), Some(icon)) =
-                                (section.get("Name"), section.get("Icon"))
-                            {
-                                map.insert(name.to_lowercase(), icon.to_string());
-                            }
+// Scene picker: apply, save the current mix under a name, delete. Scenes
+// go through the view's backend, whose locks take them for the user's own
+// changes.
+fn build_scene_bar(view: &StreamView) -> GtkBox {
+    let bar = GtkBox::new(Orientation::Horizontal, 6);
+    let store = Rc::new(SceneStore::open_default());
+
+    let names = StringList::new(&[]);
+    let reload = {
+        let store = Rc::clone(&store);
+        let names = names.clone();
+        Rc::new(move || {
+            let list = store.list().unwrap_or_else(|e| {
+                eprintln!("{}", tr!("Failed to list scenes: {}", e));
+                Vec::new()
+            });
+            let refs: Vec<&str> = list.iter().map(String::as_str).collect();
+            names.splice(0, names.n_items(), &refs);
+        })
+    };
+    reload();
+
+    let dropdown = DropDown::new(Some(names.clone()), Expression::NONE);
+    let selected_name = {
+        let dropdown = dropdown.clone();
+        move || {
+            dropdown
+                .selected_item()
+                .and_then(|obj| obj.downcast::<StringObject>().ok())
+                .map(|obj| obj.string().to_string())
+        }
+    };
+
+    let apply = Button::with_label(&tr!("Apply"));
+    {
+        let store = Rc::clone(&store);
+        let selected_name = selected_name.clone();
+        let backend = Arc::clone(&view.backend.shared);
+        apply.connect_clicked(move |_| {
+            let Some(name) = selected_name() else { return };
+            match store.load(&name) {
+                // Crossfades sleep between steps, keep them off the main loop
+                Ok(scene) => {
+                    let backend = Arc::clone(&backend);
+                    std::thread::spawn(move || {
+                        if let Err(e) = scene.apply(&*backend, SCENE_FADE) {
+                            eprintln!("{}", tr!("Failed to apply scene {}: {}", scene.name, e));
                         }
-                    }
+                    });
                 }
+                Err(e) => eprintln!("{}", tr!("Failed to load scene {}: {}", name, e)),
             }
-        }
+        });
     }
 
-    // Flatpak scalable SVGs
-    let flatpak_dirs = vec![
-        "~/.local/share/flatpak/exports/share/icons/hicolor/scalable/apps",
-        "/var/lib/flatpak/exports/share/icons/hicolor/scalable/apps",
-    ];
-    for dir in flatpak_dirs {
-        let expanded = shellexpand::tilde(dir).to_string();
-        if let Ok(entries) = std::fs::read_dir(&expanded) {
-            for entry in entries.flatten() {
-                if let Some(fname) = entry.file_name().to_str() {
-                    if fname.ends_with(".svg") {
-                        let app_id = fname.trim_end_matches(".svg");
-                        let path = entry.path().to_string_lossy().to_string();
-                        map.insert(app_id.to_lowercase(), path.clone());
-                        if let Some(stripped) = app_id.split('.').last() {
-                            map.insert(stripped.to_lowercase(), path.clone());
-                        }
+    let delete = Button::with_label(&tr!("Delete"));
+    {
+        let store = Rc::clone(&store);
+        let reload = Rc::clone(&reload);
+        delete.connect_clicked(move |_| {
+            let Some(name) = selected_name() else { return };
+            if let Err(e) = store.delete(&name) {
+                eprintln!("{}", tr!("Failed to delete scene {}: {}", name, e));
+            }
+            reload();
+        });
+    }
+
+    let entry = Entry::new();
+    entry.set_placeholder_text(Some(&tr!("Scene name")));
+    let save = Button::with_label(&tr!("Save"));
+    {
+        let entry = entry.clone();
+        let backend = Arc::clone(&view.backend.shared);
+        save.connect_clicked(move |_| {
+            let name = entry.text().trim().to_string();
+            if name.is_empty() {
+                return;
+            }
+            // listing runs pactl, so off the main loop as well
+            let backend = Arc::clone(&backend);
+            let (store, reload, entry) = (Rc::clone(&store), Rc::clone(&reload), entry.clone());
+            glib::MainContext::default().spawn_local(async move {
+                let captured = {
+                    let name = name.clone();
+                    gio::spawn_blocking(move || Scene::capture(&name, &*backend)).await
+                };
+                let Ok(captured) = captured else {
+                    log::warn!("ui: capturing scene {} panicked", name);
+                    return;
+                };
+                match captured.and_then(|scene| store.save(&scene)) {
+                    Ok(()) => {
+                        log::info!("ui: saved scene {}", name);
+                        entry.set_text("");
                     }
+                    Err(e) => eprintln!("{}", tr!("Failed to save scene {}: {}", name, e)),
                 }
-            }
-        }
+                reload();
+            });
+        });
     }
 
-    println!("Icon cache loaded: {} entries", map.len());
-    map
+    bar.append(&Label::new(Some(&tr!("Scene"))));
+    bar.append(&dropdown);
+    bar.append(&apply);
+    bar.append(&delete);
+    bar.append(&entry);
+    bar.append(&save);
+    bar
 }
 
-fn build_column(
-    backend: &Arc<Mutex<PulseAudioCli>>,
-    icons: &Arc<HashMap<String, String>>,
-    s: Stream,
-) -> GtkBox {
-    let v = GtkBox::new(Orientation::Vertical, 6);
-    let app_name = s.name.to_lowercase();
-
-    // Icon widget
-    let icon_widget = if let Some(path_or_name) = icons.get(&app_name) {
-        if std::path::Path::new(path_or_name.as_str()).exists() {
-            // File path → load and scale
-            match Pixbuf::from_file_at_size(path_or_name, 48, 48) {
-                Ok(pixbuf) => {
-                    let img = Image::from_pixbuf(Some(&pixbuf));
-                    img.set_pixel_size(48);
-                    img.set_size_request(48, 48);
-                    img
-                }
-                Err(_) => {
-                    let img = Image::from_icon_name("applications-multimedia");
-                    img.set_pixel_size(48);
-                    img.set_size_request(48, 48);
-                    img
-                }
-            }
-        } else {
-            // Theme icon name
-            let img = Image::from_icon_name(path_or_name);
-            img.set_pixel_size(48);
-            img.set_size_request(48, 48);
-            img
+fn icon_theme_name() -> String {
+    gtk4::Settings::default()
+        .and_then(|s| s.gtk_icon_theme_name())
+        .map_or_else(|| "hicolor".to_string(), |name| name.to_string())
+}
+
+// Desktop entries and the icon theme, for each stream's icon and app name.
+// Both the entries and the icon files found are cached on disk.
+struct StreamIcons {
+    apps: AppIndex,
+    locale_keys: Vec<String>,
+    theme: IconResolver,
+    theme_name: String,
+    flatpak_dirs: Vec<PathBuf>,
+    files: RefCell<LookupCache>,
+}
+
+impl StreamIcons {
+    // Reads desktop files and icon theme indexes; runs off the main loop.
+    fn load(config: &Config, theme_name: String) -> Self {
+        let apps = cache::load_app_index(&config.desktop_dirs);
+        let theme = IconResolver::new(&theme_name);
+        let mut dirs = theme.watched_dirs();
+        dirs.extend(config.flatpak_icon_dirs.iter().cloned());
+        let files = LookupCache::open(&cache::default_icons_path(), &dirs);
+        log::debug!("ui: icon cache loaded, {} entries and {} icons", apps.entries().len(), files.len());
+        StreamIcons {
+            apps,
+            locale_keys: user_locale_keys(),
+            theme,
+            theme_name,
+            flatpak_dirs: config.flatpak_icon_dirs.clone(),
+            files: RefCell::new(files),
         }
-    } else {
-        let img = Image::from_icon_name("applications-multimedia");
-        img.set_pixel_size(48);
-        img.set_size_request(48, 48);
-        img
-    };
+    }
 
-    // Label, slider, mute toggle
-    let label = Label::new(Some(&s.name));
-    label.set_xalign(0.5);
+    fn file_for(&self, name: &str, size: i32) -> Option<PathBuf> {
+        let key = format!("{}/{}/{}", self.theme_name, size, name);
+        self.files.borrow_mut().get_or_insert_with(&key, || {
+            self.theme.lookup(name, size, 1).or_else(|| {
+                self.flatpak_dirs.iter().map(|dir| dir.join(format!("{}.svg", name))).find(|p| p.is_file())
+            })
+        })
+    }
 
-    let scale = Scale::with_range(Orientation::Vertical, 0.0, 1.0, 0.01);
-    scale.set_inverted(true);
-    scale.set_draw_value(false);
-    scale.set_size_request(60, 160);
-    scale.set_value(s.volume_01 as f64);
+    // The localized name of the app playing `s`, if it has a desktop entry.
+    fn app_name(&self, s: &Stream) -> Option<String> {
+        self.apps.entry_for(s).map(|e| e.name_for(&self.locale_keys).to_string())
+    }
 
-    let mute = ToggleButton::with_label("Mute");
-    mute.set_active(s.mute);
+    // A file for the stream's icon, or else a name for GTK to look up.
+    fn icon_for(&self, s: &Stream, size: i32) -> Option<String> {
+        let name = self.apps.icon_for(s)?;
+        Some(self.file_for(&name, size).map_or(name, |path| path.to_string_lossy().to_string()))
+    }
 
-    // Slider binding
-    let id = s.id;
-    let backend1: Arc<Mutex<PulseAudioCli>> = Arc::clone(backend);
-    scale.connect_value_changed(move |sc| {
-        let val = sc.value() as f32;
-        if let Ok(b) = backend1.lock() {
-            let _ = b.set_volume(id, val);
-            println!("Set volume for {} to {}", id, val);
+    fn save(&self) {
+        if let Err(e) = self.files.borrow_mut().save() {
+            eprintln!("{}", tr!("Failed to save the icon cache: {}", e));
         }
-    });
+    }
+}
 
-    // Mute binding
-    let id2 = s.id;
-    let backend2: Arc<Mutex<PulseAudioCli>> = Arc::clone(backend);
-    mute.connect_toggled(move |btn| {
-        let active = btn.is_active();
-        if let Ok(b) = backend2.lock() {
-            let _ = b.set_mute(id2, active);
-            println!("Mute for {} set to {}", id2, active);
+// The title of an app's column: an alias, else the app's name in the
+// user's language.
+fn column_title(icons: Option<&StreamIcons>, config: &Config, s: &Stream) -> String {
+    match config.alias_for(s) {
+        Some(alias) => alias.to_string(),
+        None => icons.and_then(|i| i.app_name(s)).unwrap_or_else(|| s.name.clone()),
+    }
+}
+
+// Whether the user is holding the slider down. Captured before the
+// slider's own gestures, which would otherwise claim the release.
+fn track_hold(scale: &Scale) -> Rc<Cell<bool>> {
+    let held = Rc::new(Cell::new(false));
+    let events = EventControllerLegacy::new();
+    events.set_propagation_phase(PropagationPhase::Capture);
+    let h = Rc::clone(&held);
+    events.connect_event(move |_, event| {
+        match event.event_type() {
+            EventType::ButtonPress | EventType::TouchBegin => h.set(true),
+            EventType::ButtonRelease | EventType::TouchEnd | EventType::TouchCancel => h.set(false),
+            _ => {}
         }
+        Propagation::Proceed
     });
+    scale.add_controller(events);
+    held
+}
 
-    // Append children (GTK4)
-    v.append(&icon_widget);
-    v.append(&label);
-    v.append(&scale);
-    v.append(&mute);
+// Scrolling moves the slider by `step` per wheel notch; touchpads report
+// fractions of a notch, which move it smoothly. The slider's own scrolling
+// is replaced, so the step is the same over any part of a column.
+fn add_scroll(widget: &impl IsA<Widget>, scale: &Scale, step: f64, phase: PropagationPhase) {
+    let scroll = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+    scroll.set_propagation_phase(phase);
+    let scale = scale.clone();
+    scroll.connect_scroll(move |_, _, dy| {
+        scale.set_value(scale.value() - dy * step);
+        Propagation::Stop
+    });
+    widget.add_controller(scroll);
+}
 
-    v
+// Shows a value read from the backend without writing it back.
+fn set_quietly<W: ObjectExt>(widget: &W, handler: &SignalHandlerId, set: impl FnOnce(&W)) {
+    widget.block_signal(handler);
+    set(widget);
+    widget.unblock_signal(handler);
 }
 
-// Optional: theme fallback mapping helper
-fn icon_for_app(name: &str) -> &str {
-    match name.to_lowercase().as_str() {
-        "firefox" => "firefox",
-        "vlc" => "vlc",
-        "spotify" => "spotify",
-        "stremio" => "video-player", // fallback if no specific icon
-        _ => "applications-multimedia",
+// Locked when every stream of the group is; the tooltip tells what the
+// lock undid.
+fn show_lock(lock: &ToggleButton, backend: &BackendHandle, g: &StreamGroup) {
+    let b = &backend.shared;
+    lock.set_active(g.streams.iter().all(|m| b.inner().is_locked(m.id)));
+    let reverted = b.inner().reverts_of(&g.ids()).len();
+    if reverted > 0 {
+        let tip = trn!("Reverted {} change made by {}", "Reverted {} changes made by {}", reverted, reverted, g.first().name);
+        lock.set_tooltip_text(Some(&tip));
+    } else {
+        lock.set_tooltip_text(None);
     }
 }
 
-fn load_desktop_icons() -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    let dirs = vec![
-        "/usr/share/applications",
-        "~/.local/share/applications",
-        "~/.local/share/flatpak/exports/share/applications",
-    ];
-
-    for dir in dirs {
-        let expanded = shellexpand::tilde(dir).to_string();
-        if let Ok(entries) = fs::read_dir(&expanded) {
-            println!("Scanning directory: {}", expanded);
-            for entry in entries.flatten() {
-                if entry.path().extension().and_then(|s| s.to_str()) == Some("desktop") {
-                    let path = entry.path();
-                    if let Ok(conf) = Ini::load_from_file(&path) {
-                        if let Some(section) = conf.section(Some("Desktop Entry")) {
-                            if let (Some(name), Some(icon)) =
-                                (section.get("Name"), section.get("Icon"))
-                            {
-                                println!("Loaded desktop entry: {} -> {}", name, icon);
-                                map.insert(name.to_lowercase(), icon.to_string());
-                            }
-                        }
-                    } else {
-                        println!("Failed to parse: {:?}", path);
+/// One column per app. The slider and mute act on every stream of the
+/// group; an expander holds the individual streams when there are
+/// several. Scrolling anywhere on the column moves its slider, a middle
+/// click mutes and a double click on the title resets to 100%. Refreshes
+/// update the widgets in place, except a slider the user is holding.
+struct Column {
+    root: GtkBox,
+    label: Label,
+    scale: Scale,
+    scale_changed: SignalHandlerId,
+    held: Rc<Cell<bool>>,
+    mute: ToggleButton,
+    mute_toggled: SignalHandlerId,
+    lock: ToggleButton,
+    lock_toggled: SignalHandlerId,
+    /// As of the last refresh; the handlers act on its streams.
+    group: Rc<RefCell<StreamGroup>>,
+    members: RefCell<Option<Members>>,
+}
+
+impl Column {
+    fn new(backend: &BackendHandle, icons: Option<&StreamIcons>, config: &Config, g: StreamGroup) -> Column {
+        let s = g.first().clone();
+        let v = GtkBox::new(Orientation::Vertical, 6);
+        let size = config.icon_size;
+
+        // Icon widget
+        let icon_widget = if let Some(path_or_name) = icons.and_then(|i| i.icon_for(&s, size)) {
+            if std::path::Path::new(path_or_name.as_str()).exists() {
+                // File path → load and scale
+                match Pixbuf::from_file_at_size(&path_or_name, size, size) {
+                    Ok(pixbuf) => {
+                        let img = Image::from_pixbuf(Some(&pixbuf));
+                        img.set_pixel_size(size);
+                        img.set_size_request(size, size);
+                        img
+                    }
+                    Err(_) => {
+                        let img = Image::from_icon_name("applications-multimedia");
+                        img.set_pixel_size(size);
+                        img.set_size_request(size, size);
+                        img
                     }
                 }
+            } else {
+                // Theme icon name
+                let img = Image::from_icon_name(&path_or_name);
+                img.set_pixel_size(size);
+                img.set_size_request(size, size);
+                img
             }
         } else {
-            println!("Directory not found: {}", expanded);
+            let img = Image::from_icon_name("applications-multimedia");
+            img.set_pixel_size(size);
+            img.set_size_request(size, size);
+            img
+        };
+
+        // The icon is the handle for dragging the column elsewhere
+        let drag = DragSource::new();
+        drag.set_actions(DragAction::MOVE);
+        let dragged = app_key(&s);
+        drag.connect_prepare(move |_, _, _| Some(ContentProvider::for_value(&dragged.to_value())));
+        let column = v.clone();
+        drag.connect_drag_begin(move |src, _| {
+            src.set_icon(Some(&WidgetPaintable::new(Some(&column))), 0, 0);
+        });
+        icon_widget.add_controller(drag);
+
+        // Label, slider, mute toggle
+        let label = Label::new(Some(&column_title(icons, config, &s)));
+        label.set_xalign(0.5);
+
+        let scale = Scale::with_range(Orientation::Vertical, 0.0, 1.0, config.slider_step);
+        scale.set_increments(config.slider_step, config.page_step);
+        scale.set_inverted(true);
+        scale.set_draw_value(false);
+        scale.set_size_request(60, 160);
+        scale.set_value(g.volume_01() as f64);
+        let held = track_hold(&scale);
+
+        let mute = ToggleButton::with_label(&tr!("Mute"));
+        mute.set_active(g.mute());
+
+        let lock = ToggleButton::with_label(&tr!("Lock"));
+        show_lock(&lock, backend, &g);
+
+        // Pinned apps come first; hidden ones stay hidden until the config
+        // says otherwise
+        let pin = ToggleButton::with_label(&tr!("Pin"));
+        pin.set_active(config.is_pinned(&s));
+        {
+            let app = app_key(&s);
+            pin.connect_toggled(move |btn| {
+                let pinned = btn.is_active();
+                save_app_list("pinned", |config| {
+                    let mut apps: Vec<String> = config.pinned_apps.iter().filter(|a| **a != app).cloned().collect();
+                    if pinned {
+                        apps.push(app.clone());
+                    }
+                    apps
+                });
+            });
+        }
+        let hide = Button::with_label(&tr!("Hide"));
+        {
+            let app = app_key(&s);
+            hide.connect_clicked(move |_| {
+                save_app_list("hidden", |config| {
+                    let mut apps = config.hidden_apps.clone();
+                    if !apps.contains(&app) {
+                        apps.push(app.clone());
+                    }
+                    apps
+                });
+            });
+        }
+
+        // Slider binding: members keep the proportions they had at the
+        // last refresh, which a held slider postpones
+        let group = Rc::new(RefCell::new(g));
+        let group1 = Rc::clone(&group);
+        let backend1 = backend.clone();
+        let scale_changed = scale.connect_value_changed(move |sc| {
+            let val = sc.value() as f32;
+            for (id, vol) in group1.borrow().scaled(val) {
+                backend1.set_volume(id, vol);
+                log::debug!("ui: volume for {} set to {}", id, vol);
+            }
+        });
+
+        // Mute binding
+        let group2 = Rc::clone(&group);
+        let worker2 = Rc::clone(&backend.worker);
+        let mute_toggled = mute.connect_toggled(move |btn| {
+            let active = btn.is_active();
+            for id in group2.borrow().ids() {
+                worker2.send(Request::SetMute(id, active));
+                log::debug!("ui: mute for {} set to {}", id, active);
+            }
+        });
+
+        // Lock binding: hold the values currently shown
+        let shared3 = Arc::clone(&backend.shared);
+        let group3 = Rc::clone(&group);
+        let scale3 = scale.clone();
+        let mute3 = mute.clone();
+        let lock_toggled = lock.connect_toggled(move |btn| {
+            let b = &shared3;
+            let group3 = group3.borrow();
+            for (member, (_, vol)) in group3.streams.iter().zip(group3.scaled(scale3.value() as f32)) {
+                let mut current = member.clone();
+                current.volume_01 = vol;
+                current.mute = mute3.is_active();
+                b.inner().set_locked(&current, btn.is_active());
+                log::debug!("ui: lock for {} set to {}", current.id, btn.is_active());
+            }
+        });
+
+        // Gestures: the column's slider takes the wheel from anywhere but
+        // the member sliders, which take it themselves
+        add_scroll(&v, &scale, config.scroll_step, PropagationPhase::Bubble);
+        add_scroll(&scale, &scale, config.scroll_step, PropagationPhase::Capture);
+        // claimed before the slider or a button can take the click
+        let middle = GestureClick::new();
+        middle.set_button(2);
+        middle.set_propagation_phase(PropagationPhase::Capture);
+        let mute4 = mute.clone();
+        middle.connect_pressed(move |gesture, _, _, _| {
+            gesture.set_state(EventSequenceState::Claimed);
+            mute4.set_active(!mute4.is_active());
+        });
+        v.add_controller(middle);
+        let double = GestureClick::new();
+        let scale4 = scale.clone();
+        double.connect_pressed(move |_, n_press, _, _| {
+            if n_press == 2 {
+                scale4.set_value(1.0);
+            }
+        });
+        label.add_controller(double);
+
+        // Tab goes slider first, then the buttons; clicking one leaves the
+        // focus where the keys expect it
+        for button in [mute.upcast_ref::<Widget>(), lock.upcast_ref(), pin.upcast_ref(), hide.upcast_ref()] {
+            button.set_focus_on_click(false);
+        }
+
+        // Append children (GTK4)
+        v.add_css_class("stream-column");
+        v.append(&icon_widget);
+        v.append(&label);
+        v.append(&scale);
+        v.append(&mute);
+        v.append(&lock);
+        v.append(&pin);
+        v.append(&hide);
+
+        let column = Column {
+            root: v,
+            label,
+            scale,
+            scale_changed,
+            held,
+            mute,
+            mute_toggled,
+            lock,
+            lock_toggled,
+            group,
+            members: RefCell::new(None),
+        };
+        column.update_members(backend, config);
+        column
+    }
+
+    fn update(&self, backend: &BackendHandle, icons: Option<&StreamIcons>, config: &Config, g: StreamGroup) {
+        // the slider keeps what the user is doing, and the group the
+        // proportions it is being scaled by, until the writes settle
+        if self.held.get() || !backend.settled(&g.ids()) {
+            return;
+        }
+        self.label.set_text(&column_title(icons, config, g.first()));
+        set_quietly(&self.scale, &self.scale_changed, |scale| scale.set_value(g.volume_01() as f64));
+        set_quietly(&self.mute, &self.mute_toggled, |mute| mute.set_active(g.mute()));
+        set_quietly(&self.lock, &self.lock_toggled, |lock| show_lock(lock, backend, &g));
+        *self.group.borrow_mut() = g;
+        self.update_members(backend, config);
+    }
+
+    // Updates the member sliders, or replaces them when streams came or went.
+    fn update_members(&self, backend: &BackendHandle, config: &Config) {
+        let group = self.group.borrow();
+        let mut members = self.members.borrow_mut();
+        match &*members {
+            Some(m) if m.ids() == group.ids() => {
+                m.update(backend, &group);
+                return;
+            }
+            Some(m) if m.is_held() => return,
+            _ => {}
+        }
+        let expanded = members.as_ref().is_some_and(|m| m.expander.is_expanded());
+        if let Some(old) = members.take() {
+            self.root.remove(&old.expander);
+        }
+        if group.streams.len() > 1 {
+            let new = Members::new(backend, config, &group);
+            new.expander.set_expanded(expanded);
+            self.root.append(&new.expander);
+            *members = Some(new);
         }
     }
+}
 
-    println!("Total icons loaded: {}", map.len());
-    map
+// One stream of a group: its own slider, labeled by what it plays.
+struct MemberSlider {
+    id: u32,
+    label: Label,
+    scale: Scale,
+    changed: SignalHandlerId,
+    held: Rc<Cell<bool>>,
 }
 
-fn load_flatpak_icons() -> HashMap<String, String> {
-    let mut map = HashMap::new();
-    let dirs = vec![
-        "~/.local/share/flatpak/exports/share/icons/hicolor/128x128/apps",
-        "/var/lib/flatpak/exports/share/icons/hicolor/128x128/apps",
-    ];
-
-    for dir in dirs {
-        let expanded = shellexpand::tilde(dir).to_string();
-        if let Ok(entries) = std::fs::read_dir(expanded) {
-            for entry in entries.flatten() {
-                if let Some(fname) = entry.file_name().to_str() {
-                    if fname.ends_with(".png") {
-                        let app_id = fname.trim_end_matches(".png");
-                        // store both full ID and stripped name
-                        map.insert(
-                            app_id.to_lowercase(),
-                            entry.path().to_string_lossy().to_string(),
-                        );
-                        if let Some(stripped) = app_id.split('.').last() {
-                            map.insert(
-                                stripped.to_lowercase(),
-                                entry.path().to_string_lossy().to_string(),
-                            );
-                        }
-                    }
-                }
+// The streams of a group in an expander.
+struct Members {
+    expander: Expander,
+    sliders: Vec<MemberSlider>,
+}
+
+fn member_title(member: &Stream) -> String {
+    member.media_name.clone().unwrap_or_else(|| tr!("Stream #{}", member.id))
+}
+
+impl Members {
+    fn new(backend: &BackendHandle, config: &Config, group: &StreamGroup) -> Members {
+        let list = GtkBox::new(Orientation::Vertical, 4);
+        let mut sliders = Vec::new();
+        for member in &group.streams {
+            let title = member_title(member);
+            let label = Label::new(Some(&title));
+            label.set_xalign(0.0);
+            label.set_ellipsize(EllipsizeMode::End);
+            label.set_max_width_chars(16);
+            label.set_tooltip_text(Some(&title));
+
+            let scale = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, config.slider_step);
+            scale.set_increments(config.slider_step, config.page_step);
+            scale.set_draw_value(false);
+            scale.set_value(member.volume_01 as f64);
+            let held = track_hold(&scale);
+            add_scroll(&scale, &scale, config.scroll_step, PropagationPhase::Capture);
+            let id = member.id;
+            let backend = backend.clone();
+            let changed = scale.connect_value_changed(move |sc| {
+                let val = sc.value() as f32;
+                backend.set_volume(id, val);
+                log::debug!("ui: volume for {} set to {}", id, val);
+            });
+
+            list.append(&label);
+            list.append(&scale);
+            sliders.push(MemberSlider { id, label, scale, changed, held });
+        }
+
+        let expander = Expander::new(Some(&trn!("{} stream", "{} streams", group.streams.len(), group.streams.len())));
+        expander.set_child(Some(&list));
+        Members { expander, sliders }
+    }
+
+    fn ids(&self) -> Vec<u32> {
+        self.sliders.iter().map(|m| m.id).collect()
+    }
+
+    fn is_held(&self) -> bool {
+        self.sliders.iter().any(|m| m.held.get())
+    }
+
+    fn update(&self, backend: &BackendHandle, group: &StreamGroup) {
+        for (slider, member) in self.sliders.iter().zip(&group.streams) {
+            let title = member_title(member);
+            slider.label.set_text(&title);
+            slider.label.set_tooltip_text(Some(&title));
+            if !slider.held.get() && backend.settled(&[member.id]) {
+                set_quietly(&slider.scale, &slider.changed, |scale| scale.set_value(member.volume_01 as f64));
             }
         }
     }
-    map
 }
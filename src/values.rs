@@ -0,0 +1,51 @@
+/// Parses a volume written as a percentage ("50%" or plain "50").
+pub fn parse_percent(s: &str) -> Option<f32> {
+    let s = s.trim();
+    let num = s.strip_suffix('%').unwrap_or(s).trim();
+    let pct: f32 = num.parse().ok()?;
+    if pct.is_finite() && pct >= 0.0 { Some(pct / 100.0) } else { None }
+}
+
+pub fn format_percent(vol_01: f32) -> String {
+    format!("{}%", (vol_01 * 100.0).round() as i32)
+}
+
+pub fn parse_bool(s: &str) -> Option<bool> {
+    match s.trim().to_lowercase().as_str() {
+        "yes" | "true" | "on" | "1" => Some(true),
+        "no" | "false" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn format_bool(b: bool) -> &'static str {
+    if b { "yes" } else { "no" }
+}
+
+/// Compiles a shell-style glob (`*`, `?`) into a case-insensitive regex
+/// matching the whole string.
+pub fn glob_to_regex(glob: &str) -> regex::Regex {
+    let mut pattern = String::from("(?i)^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    regex::Regex::new(&pattern).expect("escaped glob is a valid regex")
+}
+
+/// Parses a duration written with a unit: "250ms", "4s" or "1.5s".
+pub fn parse_duration(s: &str) -> Option<std::time::Duration> {
+    let s = s.trim();
+    if let Some(ms) = s.strip_suffix("ms") {
+        ms.trim().parse().ok().map(std::time::Duration::from_millis)
+    } else if let Some(secs) = s.strip_suffix('s') {
+        let secs: f64 = secs.trim().parse().ok()?;
+        if secs.is_finite() && secs >= 0.0 { Some(std::time::Duration::from_secs_f64(secs)) } else { None }
+    } else {
+        None
+    }
+}
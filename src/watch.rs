@@ -0,0 +1,42 @@
+use std::cell::Cell;
+use std::path::Path;
+use std::rc::Rc;
+use std::time::Duration;
+
+use gtk4::gio::{self, prelude::*, Cancellable, FileMonitor, FileMonitorEvent, FileMonitorFlags};
+use gtk4::glib;
+
+// Editors save in several steps (truncate, write, rename); wait for them
+// to settle so a half-written file is not read.
+const SETTLE: Duration = Duration::from_millis(150);
+
+/// Calls `on_change` on the main loop after `path` is written, replaced or
+/// removed. The file need not exist yet. Dropping the monitor stops it.
+pub fn watch_file(path: &Path, on_change: impl Fn() + 'static) -> Option<FileMonitor> {
+    let monitor = match gio::File::for_path(path).monitor_file(FileMonitorFlags::NONE, Cancellable::NONE) {
+        Ok(m) => m,
+        Err(e) => {
+            log::warn!("cannot watch {}: {}", path.display(), e);
+            return None;
+        }
+    };
+
+    let on_change = Rc::new(on_change);
+    let pending = Rc::new(Cell::new(false));
+    monitor.connect_changed(move |_, _, _, event| {
+        let relevant = matches!(
+            event,
+            FileMonitorEvent::ChangesDoneHint | FileMonitorEvent::Created | FileMonitorEvent::Deleted
+        );
+        if !relevant || pending.replace(true) {
+            return;
+        }
+        let on_change = on_change.clone();
+        let pending = pending.clone();
+        glib::timeout_add_local_once(SETTLE, move || {
+            pending.set(false);
+            on_change();
+        });
+    });
+    Some(monitor)
+}
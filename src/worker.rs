@@ -0,0 +1,156 @@
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+
+use crate::audio::{AudioBackend, AudioError, Stream};
+use crate::rules::{RuleEngine, RuleSet};
+
+/// Work for the backend thread.
+#[derive(Debug)]
+pub enum Request {
+    /// Lists the streams and runs the rules on new ones; answered with
+    /// [`Reply::Streams`], without the streams the rules hide.
+    List,
+    SetVolume(u32, f32),
+    SetMute(u32, bool),
+    /// Swaps the rule set, see [`RuleEngine::set_rules`].
+    SetRules(RuleSet),
+}
+
+/// What the backend thread sends back.
+#[derive(Debug)]
+pub enum Reply {
+    Streams(Vec<Stream>),
+    /// A request failed; `what` names it for the log.
+    Failed { what: String, error: AudioError },
+}
+
+/// Requests not yet started. Only the latest volume and mute of a stream
+/// matter, so a new one replaces the one waiting, keeping its place; a
+/// new listing replaces one waiting as well, but goes last, so it sees
+/// every write queued before it.
+#[derive(Debug, Default)]
+pub struct RequestQueue {
+    pending: VecDeque<Request>,
+}
+
+impl RequestQueue {
+    pub fn push(&mut self, request: Request) {
+        let waiting = self.pending.iter_mut().find(|r| match (&**r, &request) {
+            (Request::SetVolume(a, _), Request::SetVolume(b, _)) => a == b,
+            (Request::SetMute(a, _), Request::SetMute(b, _)) => a == b,
+            _ => false,
+        });
+        if let Some(waiting) = waiting {
+            *waiting = request;
+            return;
+        }
+        if matches!(request, Request::List | Request::SetRules(_)) {
+            let same = std::mem::discriminant(&request);
+            self.pending.retain(|r| std::mem::discriminant(r) != same);
+        }
+        self.pending.push_back(request);
+    }
+
+    pub fn pop(&mut self) -> Option<Request> {
+        self.pending.pop_front()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    queue: RequestQueue,
+    closed: bool,
+}
+
+#[derive(Default)]
+struct Shared {
+    state: Mutex<State>,
+    wake: Condvar,
+}
+
+/// Runs backend calls on a thread of their own, so a slow sound server
+/// never holds up the caller. Requests run in the order queued; replies
+/// come back over the channel [`BackendWorker::spawn`] returns. Dropping
+/// the worker lets the thread finish what is queued and exit.
+pub struct BackendWorker {
+    shared: Arc<Shared>,
+}
+
+impl BackendWorker {
+    pub fn spawn<B>(backend: Arc<B>, rules: RuleEngine) -> (BackendWorker, Receiver<Reply>)
+    where
+        B: AudioBackend + Send + Sync + 'static,
+    {
+        let shared = Arc::new(Shared::default());
+        let (tx, rx) = mpsc::channel();
+        let worker_shared = Arc::clone(&shared);
+        thread::spawn(move || run(&*backend, rules, &worker_shared, &tx));
+        (BackendWorker { shared }, rx)
+    }
+
+    pub fn send(&self, request: Request) {
+        self.shared.state.lock().unwrap().queue.push(request);
+        self.shared.wake.notify_one();
+    }
+}
+
+impl Drop for BackendWorker {
+    fn drop(&mut self) {
+        self.shared.state.lock().unwrap().closed = true;
+        self.shared.wake.notify_one();
+    }
+}
+
+fn run<B: AudioBackend>(backend: &B, mut rules: RuleEngine, shared: &Shared, tx: &Sender<Reply>) {
+    loop {
+        let request = {
+            let mut state = shared.state.lock().unwrap();
+            loop {
+                if let Some(request) = state.queue.pop() {
+                    break request;
+                }
+                if state.closed {
+                    return;
+                }
+                state = shared.wake.wait(state).unwrap();
+            }
+        };
+
+        let failed = |what: String, error| Some(Reply::Failed { what, error });
+        let reply = match request {
+            Request::List => match backend.list_streams() {
+                Ok(mut streams) => {
+                    rules.process(&streams, backend);
+                    streams.retain(|s| !rules.is_hidden(s.id));
+                    Some(Reply::Streams(streams))
+                }
+                Err(e) => failed("list streams".into(), e),
+            },
+            Request::SetVolume(id, vol) => {
+                backend.set_volume(id, vol).err().and_then(|e| failed(format!("set volume of #{}", id), e))
+            }
+            Request::SetMute(id, mute) => {
+                backend.set_mute(id, mute).err().and_then(|e| failed(format!("set mute of #{}", id), e))
+            }
+            Request::SetRules(set) => {
+                rules.set_rules(set);
+                None
+            }
+        };
+        // writes queued before the worker was dropped still go out, even
+        // with nobody left listening
+        if let Some(reply) = reply {
+            let _ = tx.send(reply);
+        }
+    }
+}
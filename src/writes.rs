@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Copy)]
+struct Slot {
+    sent_at: Instant,
+    /// Set while the interval since the last write has not passed.
+    pending: Option<f32>,
+}
+
+/// Paces the volume writes a slider makes while it moves. Each stream
+/// gets at most one write per `interval`; values in between are held back
+/// and only the last of them goes out once the interval has passed, so
+/// the final position always reaches the server.
+///
+/// Only changes the user makes belong here: values from a refresh are
+/// shown with the slider's handler blocked. A refresh can still carry
+/// what the server had before a write landed, so server values for a
+/// stream are ignored until `settle` after its last write.
+#[derive(Debug, Clone)]
+pub struct WriteCoordinator {
+    interval: Duration,
+    settle: Duration,
+    streams: HashMap<u32, Slot>,
+}
+
+impl WriteCoordinator {
+    pub fn new(interval: Duration, settle: Duration) -> Self {
+        WriteCoordinator { interval, settle, streams: HashMap::new() }
+    }
+
+    /// A volume the user chose. Returns it when it may be written now;
+    /// otherwise it waits for [`WriteCoordinator::due`].
+    pub fn user_set(&mut self, stream_id: u32, vol_01: f32, now: Instant) -> Option<f32> {
+        match self.streams.get_mut(&stream_id) {
+            Some(slot) if now.duration_since(slot.sent_at) < self.interval => {
+                slot.pending = Some(vol_01);
+                None
+            }
+            _ => {
+                self.streams.insert(stream_id, Slot { sent_at: now, pending: None });
+                Some(vol_01)
+            }
+        }
+    }
+
+    /// Held back values whose interval has passed, to be written now.
+    pub fn due(&mut self, now: Instant) -> Vec<(u32, f32)> {
+        let mut due = Vec::new();
+        for (&id, slot) in self.streams.iter_mut() {
+            if now.duration_since(slot.sent_at) < self.interval {
+                continue;
+            }
+            if let Some(vol) = slot.pending.take() {
+                slot.sent_at = now;
+                due.push((id, vol));
+            }
+        }
+        // streams done settling need no slot
+        let settle = self.settle;
+        self.streams.retain(|_, slot| slot.pending.is_some() || now.duration_since(slot.sent_at) < settle);
+        due
+    }
+
+    /// Whether a value the server reports for the stream can be shown:
+    /// nothing is waiting to be written and the last write has settled.
+    pub fn settled(&self, stream_id: u32, now: Instant) -> bool {
+        self.streams
+            .get(&stream_id)
+            .is_none_or(|slot| slot.pending.is_none() && now.duration_since(slot.sent_at) >= self.settle)
+    }
+}
@@ -0,0 +1,44 @@
+use std::env;
+use std::path::PathBuf;
+
+fn base_dir(var: &str, fallback: &str) -> PathBuf {
+    match env::var(var) {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(shellexpand::tilde(fallback).to_string()),
+    }
+}
+
+/// `$XDG_CONFIG_HOME/wlvolctl`
+pub fn config_dir() -> PathBuf {
+    base_dir("XDG_CONFIG_HOME", "~/.config").join("wlvolctl")
+}
+
+/// `$XDG_STATE_HOME/wlvolctl`
+pub fn state_dir() -> PathBuf {
+    base_dir("XDG_STATE_HOME", "~/.local/state").join("wlvolctl")
+}
+
+/// `$XDG_CACHE_HOME/wlvolctl`
+pub fn cache_dir() -> PathBuf {
+    base_dir("XDG_CACHE_HOME", "~/.cache").join("wlvolctl")
+}
+
+/// `$XDG_RUNTIME_DIR/wlvolctl`, or the state directory without one.
+pub fn runtime_dir() -> PathBuf {
+    match env::var("XDG_RUNTIME_DIR") {
+        Ok(dir) if !dir.is_empty() => PathBuf::from(dir).join("wlvolctl"),
+        _ => state_dir(),
+    }
+}
+
+/// `$XDG_DATA_HOME` followed by `$XDG_DATA_DIRS`, most important first.
+/// These are the shared base directories, not wlvolctl's own.
+pub fn data_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![base_dir("XDG_DATA_HOME", "~/.local/share")];
+    let system = match env::var("XDG_DATA_DIRS") {
+        Ok(list) if !list.is_empty() => list,
+        _ => "/usr/local/share:/usr/share".to_string(),
+    };
+    dirs.extend(system.split(':').filter(|d| !d.is_empty()).map(PathBuf::from));
+    dirs
+}
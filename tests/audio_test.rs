@@ -14,6 +14,12 @@ impl AudioBackend for DummyBackend {
                 icon_name: Some("firefox".to_string()),
                 volume_01: 0.5,
                 mute: false,
+                device_id: None,
+                binary: Some("firefox".to_string()),
+                role: None,
+                media_name: None,
+                app_id: None,
+                pid: None,
                 backend_tag: BackendTag::PipeWire,
             }
         ])
@@ -0,0 +1,117 @@
+mod common;
+
+use std::cell::{Cell, RefCell};
+use std::io::{self, Write};
+use std::sync::mpsc::{self, Sender};
+
+use common::{device, stream, DeviceBuilder, MockBackend, StreamBuilder};
+use wlvolctl::audio::{AudioBackend, AudioError, BackendEvent, Device, Stream, Subscription};
+use wlvolctl::bar::{default_device, render, run, scroll, toggle_mute};
+
+#[test]
+fn test_render() {
+    let streams = vec![stream(1, "Firefox <beta>", 0.8, 0).muted(), stream(2, "mpv", 1.0, 0)];
+    let speakers = device(0, "speakers", 0.45);
+
+    let out = render(Some(&speakers), &streams);
+    assert_eq!(out["text"], "45%");
+    assert_eq!(out["percentage"], 45);
+    assert_eq!(out["class"], "unmuted");
+    let tooltip = out["tooltip"].as_str().unwrap();
+    assert!(tooltip.contains("Firefox &lt;beta&gt;: 80% (muted)"));
+    assert!(tooltip.contains("mpv: 100%"));
+
+    let out = render(Some(&speakers.muted()), &[]);
+    assert_eq!(out["text"], "muted");
+    assert_eq!(out["class"], "muted");
+
+    assert_eq!(render(None, &[])["text"], "no output");
+}
+
+#[test]
+fn test_scroll_and_mute_default_device() {
+    let backend = MockBackend::new();
+
+    scroll(&backend, 0.05).unwrap();
+    scroll(&backend, 0.05).unwrap();
+    scroll(&backend, -0.2).unwrap();
+    toggle_mute(&backend).unwrap();
+
+    let devices = backend.list_devices().unwrap();
+    let d = default_device(&devices).unwrap();
+    assert_eq!(d.id, 0);
+    assert!((d.volume_01 - 0.4).abs() < 0.001);
+    assert!(d.mute);
+    // other outputs are left alone
+    assert!((devices[1].volume_01 - 0.9).abs() < 0.001);
+}
+
+/// A server that restarts: the first event stream ends at once, the next
+/// one reports a change. Every listing shows a new volume.
+struct Restarting {
+    inner: MockBackend,
+    subscribes: Cell<u32>,
+    senders: RefCell<Vec<Sender<BackendEvent>>>,
+}
+
+impl AudioBackend for Restarting {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        let mut streams = self.inner.list_streams()?;
+        streams[0].volume_01 -= 0.1;
+        self.inner.set_volume(streams[0].id, streams[0].volume_01)?;
+        Ok(streams)
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.inner.set_volume(stream_id, vol_01)
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.inner.set_mute(stream_id, mute)
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        self.inner.list_devices()
+    }
+
+    fn subscribe(&self) -> Result<Subscription, AudioError> {
+        self.subscribes.set(self.subscribes.get() + 1);
+        let (tx, rx) = mpsc::channel();
+        if self.subscribes.get() > 1 {
+            tx.send(BackendEvent::ServerChanged).unwrap();
+            self.senders.borrow_mut().push(tx);
+        }
+        Ok(rx.into())
+    }
+}
+
+/// Takes `limit` lines, then fails like a closed pipe.
+struct Lines {
+    text: String,
+    limit: usize,
+}
+
+impl Write for Lines {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.text.lines().count() >= self.limit {
+            return Err(io::ErrorKind::BrokenPipe.into());
+        }
+        self.text.push_str(&String::from_utf8_lossy(buf));
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test_run_survives_server_restart() {
+    let backend = Restarting { inner: MockBackend::new(), subscribes: Cell::new(0), senders: RefCell::new(Vec::new()) };
+    let mut out = Lines { text: String::new(), limit: 3 };
+
+    // only the closed stdout ends it
+    assert!(run(&backend, &mut out).is_err());
+    assert_eq!(out.text.lines().count(), 3);
+    assert_eq!(backend.subscribes.get(), 2);
+}
@@ -0,0 +1,94 @@
+use std::cell::Cell;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use wlvolctl::cache::{load_entries_cached, LookupCache};
+
+fn temp_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("wlvolctl-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// Timestamps can be too coarse to tell two quick changes apart.
+fn touch(dir: &Path, secs: u64) {
+    File::open(dir).unwrap().set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+}
+
+fn desktop_file(dir: &Path, id: &str, name: &str) {
+    fs::write(dir.join(format!("{}.desktop", id)), format!("[Desktop Entry]\nName={}\nIcon={}\n", name, id)).unwrap();
+}
+
+#[test]
+fn test_entries_cache() {
+    let root = temp_dir("cache-entries");
+    let apps = root.join("applications");
+    fs::create_dir_all(&apps).unwrap();
+    desktop_file(&apps, "mpv", "mpv");
+    touch(&apps, 1000);
+    let cache = root.join("cache/desktop-entries.json");
+    let dirs = vec![apps.clone(), root.join("missing")];
+
+    let entries = load_entries_cached(&dirs, &cache);
+    assert_eq!(entries.len(), 1);
+    assert!(cache.exists());
+
+    // an edit that leaves the directory alone is not noticed...
+    desktop_file(&apps, "mpv", "Renamed");
+    touch(&apps, 1000);
+    assert_eq!(load_entries_cached(&dirs, &cache)[0].name, "mpv");
+
+    // ...but adding a file is
+    desktop_file(&apps, "vlc", "VLC");
+    touch(&apps, 2000);
+    let mut names: Vec<String> = load_entries_cached(&dirs, &cache).into_iter().map(|e| e.name).collect();
+    names.sort();
+    assert_eq!(names, vec!["Renamed", "VLC"]);
+
+    // and so is a directory that shows up
+    fs::create_dir_all(root.join("missing")).unwrap();
+    desktop_file(&root.join("missing"), "steam", "Steam");
+    assert_eq!(load_entries_cached(&dirs, &cache).len(), 3);
+
+    // a damaged cache is rebuilt
+    fs::write(&cache, "{not json").unwrap();
+    assert_eq!(load_entries_cached(&dirs, &cache).len(), 3);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_lookup_cache() {
+    let root = temp_dir("cache-lookups");
+    let icons = root.join("icons");
+    fs::create_dir_all(&icons).unwrap();
+    touch(&icons, 1000);
+    let path = root.join("icons.json");
+    let dirs = vec![icons.clone()];
+    let calls = Cell::new(0);
+    let lookup = |found: Option<&str>| {
+        calls.set(calls.get() + 1);
+        found.map(PathBuf::from)
+    };
+
+    let mut cache = LookupCache::open(&path, &dirs);
+    assert!(cache.is_empty());
+    assert_eq!(cache.get_or_insert_with("mpv", || lookup(Some("/icons/mpv.png"))), Some("/icons/mpv.png".into()));
+    assert_eq!(cache.get_or_insert_with("gone", || lookup(None)), None);
+    assert_eq!(cache.get_or_insert_with("mpv", || lookup(None)), Some("/icons/mpv.png".into()));
+    assert_eq!(calls.get(), 2);
+    cache.save().unwrap();
+
+    // hits and misses both survive a restart
+    let mut cache = LookupCache::open(&path, &dirs);
+    assert_eq!(cache.len(), 2);
+    assert_eq!(cache.get_or_insert_with("gone", || lookup(Some("/icons/gone.png"))), None);
+    assert_eq!(calls.get(), 2);
+
+    touch(&icons, 2000);
+    let mut cache = LookupCache::open(&path, &dirs);
+    assert!(cache.is_empty());
+    assert_eq!(cache.get_or_insert_with("gone", || lookup(Some("/icons/gone.png"))), Some("/icons/gone.png".into()));
+    fs::remove_dir_all(root).unwrap();
+}
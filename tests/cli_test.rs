@@ -0,0 +1,103 @@
+mod common;
+
+use common::MockBackend;
+use wlvolctl::audio::AudioBackend;
+use wlvolctl::cli::{parse_args, run, CliError, Command, MuteAction, VolumeSpec};
+
+fn args(line: &str) -> Vec<String> {
+    line.split_whitespace().map(str::to_string).collect()
+}
+
+fn run_line(backend: &MockBackend, line: &str) -> Result<String, CliError> {
+    let inv = parse_args(&args(line))?;
+    let mut out = Vec::new();
+    run(&inv, backend, &mut out)?;
+    Ok(String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn test_parse_args() {
+    let inv = parse_args(&args("--json set firefox -5%")).unwrap();
+    assert!(inv.json);
+    assert_eq!(inv.command, Command::Set("firefox".into(), VolumeSpec::Relative(-0.05)));
+
+    let inv = parse_args(&args("toggle 42")).unwrap();
+    assert!(!inv.json);
+    assert_eq!(inv.command, Command::Mute("42".into(), MuteAction::Toggle));
+
+    assert!(matches!(parse_args(&args("set firefox loud")), Err(CliError::Usage(_))));
+    assert!(matches!(parse_args(&args("--frobnicate")), Err(CliError::Usage(_))));
+    assert!(matches!(parse_args(&args("mute colour=red")), Err(CliError::Selector(_))));
+    assert!(matches!(parse_args(&[]), Err(CliError::Usage(_))));
+}
+
+#[test]
+fn test_volume_spec() {
+    assert_eq!(VolumeSpec::parse("50%"), Some(VolumeSpec::Absolute(0.5)));
+    assert_eq!(VolumeSpec::parse("+5"), Some(VolumeSpec::Relative(0.05)));
+    assert_eq!(VolumeSpec::Relative(0.1).apply_to(0.95), 1.0);
+    assert_eq!(VolumeSpec::Relative(-0.1).apply_to(0.05), 0.0);
+}
+
+#[test]
+fn test_set_mute_and_move() {
+    let backend = MockBackend::new();
+
+    run_line(&backend, "set FIREFOX 40%").unwrap();
+    run_line(&backend, "set 2 -25%").unwrap();
+    run_line(&backend, "toggle mpv").unwrap();
+    run_line(&backend, "move firefox head").unwrap();
+
+    let streams = backend.list_streams().unwrap();
+    assert!((streams[0].volume_01 - 0.4).abs() < 0.001);
+    assert!((streams[1].volume_01 - 0.75).abs() < 0.001);
+    assert!(streams[1].mute);
+    assert_eq!(streams[0].device_id, Some(1));
+
+    run_line(&backend, "unmute name~=^mp|device=headset").unwrap();
+    let streams = backend.list_streams().unwrap();
+    assert!(!streams[1].mute);
+
+    assert!(matches!(run_line(&backend, "mute vlc"), Err(CliError::NoStream(_))));
+    assert!(matches!(run_line(&backend, "move mpv e"), Err(CliError::AmbiguousDevice(..))));
+}
+
+#[test]
+fn test_json_output() {
+    let backend = MockBackend::new();
+
+    let out = run_line(&backend, "--json list").unwrap();
+    let list: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(list[0]["name"], "Firefox");
+    assert_eq!(list[0]["percent"], 80);
+    assert_eq!(list.as_array().unwrap().len(), 2);
+
+    let out = run_line(&backend, "--json mute firefox").unwrap();
+    let changed: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(changed.as_array().unwrap().len(), 1);
+    assert_eq!(changed[0]["mute"], true);
+
+    let out = run_line(&backend, "--json devices").unwrap();
+    let devices: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(devices[1]["name"], "headset");
+}
+
+#[test]
+fn test_osd_feedback() {
+    let backend = MockBackend::new();
+    let feedback = |line: &str| {
+        let inv = parse_args(&args(line)).unwrap();
+        run(&inv, &backend, &mut Vec::new()).unwrap()
+    };
+
+    assert!(feedback("set firefox +10%").is_empty());
+
+    let shown = feedback("--osd set firefox -30%");
+    assert_eq!(shown.len(), 1);
+    assert_eq!(shown[0].title, "Firefox");
+    assert_eq!(shown[0].percent(), 60);
+
+    let shown = feedback("--osd bar toggle-mute");
+    assert!(shown[0].muted);
+    assert!(feedback("--osd list").is_empty());
+}
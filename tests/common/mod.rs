@@ -0,0 +1,214 @@
+// Shared helpers for the integration tests.
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+
+use wlvolctl::audio::{AudioBackend, AudioError, BackendTag, Device, Stream};
+
+/// In-memory backend: setters update the stored streams and devices.
+pub struct MockBackend {
+    pub streams: RefCell<Vec<Stream>>,
+    pub devices: RefCell<Vec<Device>>,
+}
+
+pub fn stream(id: u32, name: &str, vol: f32, device_id: u32) -> Stream {
+    Stream {
+        id,
+        name: name.to_string(),
+        icon_name: None,
+        volume_01: vol,
+        mute: false,
+        device_id: Some(device_id),
+        binary: None,
+        role: None,
+        media_name: None,
+        app_id: None,
+        pid: None,
+        backend_tag: BackendTag::PulseAudio,
+    }
+}
+
+/// A Flatpak app's stream, known by its app id.
+pub fn flatpak_stream(id: u32, name: &str, app_id: &str) -> Stream {
+    stream(id, name, 1.0, 0).with_app_id(app_id)
+}
+
+/// A notification or UI sound.
+pub fn event_sound(id: u32, name: &str) -> Stream {
+    stream(id, name, 1.0, 0).with_role("event")
+}
+
+/// Setters for the stream properties a test is about.
+pub trait StreamBuilder {
+    fn with_binary(self, binary: &str) -> Self;
+    fn with_app_id(self, app_id: &str) -> Self;
+    fn with_role(self, role: &str) -> Self;
+    fn with_icon(self, icon_name: &str) -> Self;
+    fn with_pid(self, pid: u32) -> Self;
+    fn muted(self) -> Self;
+}
+
+impl StreamBuilder for Stream {
+    fn with_binary(self, binary: &str) -> Self {
+        Stream { binary: Some(binary.to_string()), ..self }
+    }
+
+    fn with_app_id(self, app_id: &str) -> Self {
+        Stream { app_id: Some(app_id.to_string()), ..self }
+    }
+
+    fn with_role(self, role: &str) -> Self {
+        Stream { role: Some(role.to_string()), ..self }
+    }
+
+    fn with_icon(self, icon_name: &str) -> Self {
+        Stream { icon_name: Some(icon_name.to_string()), ..self }
+    }
+
+    fn with_pid(self, pid: u32) -> Self {
+        Stream { pid: Some(pid), ..self }
+    }
+
+    fn muted(self) -> Self {
+        Stream { mute: true, ..self }
+    }
+}
+
+pub fn device(id: u32, name: &str, vol: f32) -> Device {
+    Device {
+        id,
+        name: name.to_string(),
+        description: name.to_string(),
+        volume_01: vol,
+        mute: false,
+        is_default: id == 0,
+        backend_tag: BackendTag::PulseAudio,
+    }
+}
+
+pub trait DeviceBuilder {
+    fn with_description(self, description: &str) -> Self;
+    fn muted(self) -> Self;
+}
+
+impl DeviceBuilder for Device {
+    fn with_description(self, description: &str) -> Self {
+        Device { description: description.to_string(), ..self }
+    }
+
+    fn muted(self) -> Self {
+        Device { mute: true, ..self }
+    }
+}
+
+impl MockBackend {
+    /// Firefox and mpv playing on "speakers", with a "headset" as well.
+    pub fn new() -> Self {
+        MockBackend::with(
+            vec![stream(1, "Firefox", 0.8, 0), stream(2, "mpv", 1.0, 0)],
+            vec![device(0, "speakers", 0.5), device(1, "headset", 0.9)],
+        )
+    }
+
+    pub fn with(streams: Vec<Stream>, devices: Vec<Device>) -> Self {
+        MockBackend { streams: RefCell::new(streams), devices: RefCell::new(devices) }
+    }
+}
+
+impl AudioBackend for MockBackend {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        Ok(self.streams.borrow().clone())
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        for s in self.streams.borrow_mut().iter_mut().filter(|s| s.id == stream_id) {
+            s.volume_01 = vol_01;
+        }
+        Ok(())
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        for s in self.streams.borrow_mut().iter_mut().filter(|s| s.id == stream_id) {
+            s.mute = mute;
+        }
+        Ok(())
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        Ok(self.devices.borrow().clone())
+    }
+
+    fn set_device_volume(&self, device_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        for d in self.devices.borrow_mut().iter_mut().filter(|d| d.id == device_id) {
+            d.volume_01 = vol_01;
+        }
+        Ok(())
+    }
+
+    fn set_device_mute(&self, device_id: u32, mute: bool) -> Result<(), AudioError> {
+        for d in self.devices.borrow_mut().iter_mut().filter(|d| d.id == device_id) {
+            d.mute = mute;
+        }
+        Ok(())
+    }
+
+    fn move_stream(&self, stream_id: u32, device_id: u32) -> Result<(), AudioError> {
+        for s in self.streams.borrow_mut().iter_mut().filter(|s| s.id == stream_id) {
+            s.device_id = Some(device_id);
+        }
+        Ok(())
+    }
+}
+
+/// A [`MockBackend`] whose stream `gone` has ended by the time it is
+/// written to: every change to it fails.
+pub struct VanishingBackend {
+    pub inner: MockBackend,
+    pub gone: u32,
+}
+
+impl VanishingBackend {
+    pub fn new(gone: u32) -> Self {
+        VanishingBackend { inner: MockBackend::new(), gone }
+    }
+
+    fn check(&self, stream_id: u32) -> Result<(), AudioError> {
+        if stream_id == self.gone {
+            return Err(AudioError::CommandFailed("no such entity".into()));
+        }
+        Ok(())
+    }
+}
+
+impl AudioBackend for VanishingBackend {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        self.inner.list_streams()
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.check(stream_id)?;
+        self.inner.set_volume(stream_id, vol_01)
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.check(stream_id)?;
+        self.inner.set_mute(stream_id, mute)
+    }
+
+    fn list_devices(&self) -> Result<Vec<Device>, AudioError> {
+        self.inner.list_devices()
+    }
+
+    fn set_device_volume(&self, device_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        self.inner.set_device_volume(device_id, vol_01)
+    }
+
+    fn set_device_mute(&self, device_id: u32, mute: bool) -> Result<(), AudioError> {
+        self.inner.set_device_mute(device_id, mute)
+    }
+
+    fn move_stream(&self, stream_id: u32, device_id: u32) -> Result<(), AudioError> {
+        self.check(stream_id)?;
+        self.inner.move_stream(stream_id, device_id)
+    }
+}
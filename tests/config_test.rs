@@ -0,0 +1,136 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+mod common;
+
+use common::{event_sound, stream};
+use wlvolctl::config::{set_value, Config, ConfigError, Theme};
+use wlvolctl::keys::Action;
+use wlvolctl::values::parse_duration;
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("4s"), Some(Duration::from_secs(4)));
+    assert_eq!(parse_duration("1.5s"), Some(Duration::from_millis(1500)));
+    assert_eq!(parse_duration(" 250ms "), Some(Duration::from_millis(250)));
+    assert_eq!(parse_duration("250"), None);
+    assert_eq!(parse_duration("-1s"), None);
+}
+
+#[test]
+fn test_parse_config() {
+    let config = Config::parse(
+        "[general]\nrefresh_interval = 1s\n\n[popup]\nmin_open_time = 500ms\n\n\
+         [appearance]\nicon_size = 32\nslider_step = 0.05\nscroll_step = 0.02\n\n[paths]\ndesktop_dirs = /opt/apps:/usr/share/applications\n",
+    )
+    .unwrap();
+    assert_eq!(config.refresh_interval, Duration::from_secs(1));
+    assert_eq!(config.popup_min_open, Duration::from_millis(500));
+    assert_eq!(config.icon_size, 32);
+    assert_eq!(config.slider_step, 0.05);
+    assert_eq!(config.scroll_step, 0.02);
+    assert_eq!(config.desktop_dirs, vec![PathBuf::from("/opt/apps"), PathBuf::from("/usr/share/applications")]);
+    // untouched keys keep their defaults
+    assert_eq!(config.flatpak_icon_dirs, Config::default().flatpak_icon_dirs);
+
+    assert_eq!(Config::parse("").unwrap(), Config::default());
+    assert_eq!(Config::default().refresh_interval, Duration::from_secs(4));
+}
+
+#[test]
+fn test_apps_and_aliases() {
+    let config = Config::parse(
+        "[appearance]\ntheme = Dark\n\n[apps]\nhidden = Speech-Dispatcher, , steam\n\n[aliases]\nFirefox = Browser\n",
+    )
+    .unwrap();
+    assert_eq!(config.theme, Theme::Dark);
+    assert_eq!(config.hidden_apps, vec!["speech-dispatcher", "steam"]);
+    assert!(config.hides(&stream(1, "speech-dispatcher", 1.0, 0)));
+    assert!(!config.hides(&stream(2, "Firefox", 1.0, 0)));
+    assert_eq!(config.label_for(&stream(2, "firefox", 1.0, 0)), "Browser");
+    assert_eq!(config.label_for(&stream(3, "mpv", 1.0, 0)), "mpv");
+
+    assert!(Config::parse("[appearance]\ntheme = purple\n").is_err());
+    assert_eq!(Config::default().theme, Theme::System);
+}
+
+#[test]
+fn test_stream_order() {
+    let config = Config::parse("[apps]\npinned = spotify\norder = mpv, Firefox, gone\nhide_events = yes\n").unwrap();
+    let mut streams = vec![
+        stream(1, "Firefox", 1.0, 0),
+        stream(2, "discord", 1.0, 0),
+        stream(3, "mpv", 1.0, 0),
+        stream(4, "Spotify", 1.0, 0),
+        stream(5, "firefox", 1.0, 0),
+    ];
+    config.sort_streams(&mut streams);
+    let ids: Vec<u32> = streams.iter().map(|s| s.id).collect();
+    assert_eq!(ids, vec![4, 3, 1, 5, 2]);
+
+    assert!(config.hides(&event_sound(6, "Firefox")));
+    assert!(!config.hides(&streams[0]));
+
+    let shown: Vec<String> = ["mpv", "firefox", "discord"].map(String::from).to_vec();
+    assert_eq!(config.moved(&shown, "discord", "mpv"), vec!["discord", "mpv", "firefox", "gone"]);
+    assert_eq!(config.moved(&shown, "mpv", "discord"), vec!["firefox", "discord", "mpv", "gone"]);
+}
+
+#[test]
+fn test_set_value() {
+    let text = "# volumes\n[general]\nrefresh_interval = 1s\n\n[apps]\n; keep\nhidden = steam\n";
+    assert_eq!(
+        set_value(text, "apps", "hidden", "steam, discord"),
+        "# volumes\n[general]\nrefresh_interval = 1s\n\n[apps]\n; keep\nhidden = steam, discord\n"
+    );
+    assert_eq!(
+        set_value(text, "general", "refresh_interval", "2s"),
+        "# volumes\n[general]\nrefresh_interval = 2s\n\n[apps]\n; keep\nhidden = steam\n"
+    );
+    assert_eq!(
+        set_value(text, "general", "theme_unused", "x"),
+        "# volumes\n[general]\nrefresh_interval = 1s\ntheme_unused = x\n\n[apps]\n; keep\nhidden = steam\n"
+    );
+    assert_eq!(set_value("", "apps", "order", "mpv"), "[apps]\norder = mpv\n");
+    assert_eq!(
+        set_value("[general]\nrefresh_interval = 1s\n", "apps", "pinned", "mpv"),
+        "[general]\nrefresh_interval = 1s\n\n[apps]\npinned = mpv\n"
+    );
+
+    let config = Config::parse(&set_value("", "apps", "pinned", "Spotify, mpv")).unwrap();
+    assert_eq!(config.pinned_apps, vec!["spotify", "mpv"]);
+}
+
+#[test]
+fn test_config_errors() {
+    let err = Config::parse("[general]\nrefresh_interval = 4\n").unwrap_err();
+    assert!(matches!(err, ConfigError::Invalid { .. }));
+    assert!(err.to_string().starts_with("[general] refresh_interval: expected a duration"));
+
+    let err = Config::parse("[appearance]\nicon_size = 4000\n").unwrap_err();
+    assert!(err.to_string().contains("from 8 to 512"));
+    assert!(Config::parse("[appearance]\nslider_step = 0\n").is_err());
+    assert!(Config::parse("[appearance]\nscroll_step = 0.8\n").is_err());
+    assert!(Config::parse("[general]\nrefresh_interval = 10ms\n").is_err());
+
+    let err = Config::parse("[general]\nicon_size = 32\n").unwrap_err();
+    assert_eq!(err.to_string(), "[general] icon_size: belongs in [appearance]");
+    assert!(matches!(Config::parse("[popup]\ncolour = red\n"), Err(ConfigError::UnknownKey { .. })));
+    assert!(matches!(Config::parse("[colours]\n"), Err(ConfigError::UnknownSection(_))));
+    assert!(matches!(Config::parse("icon_size = 32\n"), Err(ConfigError::Syntax(_))));
+
+    let err = Config::parse("[keys]\nmute = Super+m\n").unwrap_err();
+    assert!(err.to_string().starts_with("[keys] mute: expected Ctrl or Alt"));
+    assert!(matches!(Config::parse("[keys]\nlouder = k\n"), Err(ConfigError::UnknownKey { .. })));
+}
+
+#[test]
+fn test_keys() {
+    let config = Config::parse("[appearance]\npage_step = 0.2\n\n[keys]\nmute = Ctrl+m\nclose =\n").unwrap();
+    assert_eq!(config.page_step, 0.2);
+    assert_eq!(config.keys.action_for("m", true, false), Some(Action::ToggleMute));
+    assert_eq!(config.keys.action_for("m", false, false), None);
+    assert_eq!(config.keys.action_for("Escape", false, false), None);
+    // the others keep their defaults
+    assert_eq!(config.keys.action_for("Up", false, false), Some(Action::VolumeUp));
+}
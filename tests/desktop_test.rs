@@ -0,0 +1,120 @@
+mod common;
+
+use common::{flatpak_stream, stream, StreamBuilder};
+use std::collections::HashMap;
+
+use wlvolctl::audio::Stream;
+use wlvolctl::desktop::{locale_keys, AppIndex, DesktopEntry};
+
+fn entry(id: &str, text: &str) -> DesktopEntry {
+    DesktopEntry::parse(id, &format!("[Desktop Entry]\nType=Application\n{}", text)).unwrap()
+}
+
+fn index() -> AppIndex {
+    AppIndex::new(vec![
+        entry("firefox", "Name=Firefox Web Browser\nIcon=firefox\nExec=firefox %u\nStartupWMClass=firefox\n"),
+        entry(
+            "google-chrome",
+            "Name=Google Chrome\nIcon=google-chrome\nExec=/usr/bin/google-chrome-stable %U\nStartupWMClass=Google-chrome\n",
+        ),
+        entry(
+            "com.spotify.Client",
+            "Name=Spotify\nIcon=com.spotify.Client\nExec=/usr/bin/flatpak run --branch=stable com.spotify.Client\n",
+        ),
+        entry("mpv", "Name=mpv Media Player\nIcon=mpv\nExec=env LC_ALL=C \"/opt/mpv/bin/mpv\" --player-operation-mode=pseudo-gui\n"),
+        entry("Discord", "Name=Discord\nIcon=discord\nExec=Discord\n"),
+    ])
+}
+
+// A stream whose app is known only by the binary that started it.
+fn launched(id: u32, name: &str, binary: &str) -> Stream {
+    stream(id, name, 1.0, 0).with_binary(binary)
+}
+
+#[test]
+fn test_parse_entry() {
+    let e = entry("mpv", "Name=mpv\nExec=env LC_ALL=C \"/opt/mpv/bin/mpv\" -- %U\nIcon=mpv\n");
+    assert_eq!(e.program.as_deref(), Some("mpv"));
+    assert_eq!(e.icon.as_deref(), Some("mpv"));
+
+    let e = entry("path", "Name=Path\nExec=C:\\\\odd\\\\path\n");
+    assert_eq!(e.program.as_deref(), Some("C:\\\\odd\\\\path"));
+
+    assert!(DesktopEntry::parse("hidden", "[Desktop Entry]\nName=Gone\nHidden=true\n").is_none());
+    assert!(DesktopEntry::parse("nameless", "[Desktop Entry]\nIcon=x\n").is_none());
+}
+
+#[test]
+fn test_entry_for_stream() {
+    let index = index();
+    let id_of = |s| index.entry_for(&s).map(|e| e.id.clone());
+
+    // "Firefox" is not the entry's name, but the binary finds it
+    assert_eq!(id_of(launched(1, "Firefox", "firefox")), Some("firefox".into()));
+
+    // Chromium-based: the binary matches neither name nor id, only Exec
+    assert_eq!(id_of(launched(2, "Chromium", "google-chrome-stable")), Some("google-chrome".into()));
+
+    let spotify = flatpak_stream(3, "spotify", "com.spotify.Client").with_binary("spotify");
+    assert_eq!(id_of(spotify), Some("com.spotify.Client".into()));
+
+    assert_eq!(id_of(launched(4, "mpv", "mpv")), Some("mpv".into()));
+
+    // display name last, via StartupWMClass or Name
+    assert_eq!(id_of(stream(5, "google-chrome", 1.0, 0)), Some("google-chrome".into()));
+    assert_eq!(id_of(stream(6, "discord", 1.0, 0)), Some("Discord".into()));
+    assert_eq!(id_of(stream(7, "Unknown", 1.0, 0)), None);
+}
+
+#[test]
+fn test_icon_for_stream() {
+    let index = index();
+
+    let named = launched(1, "Firefox", "firefox").with_icon("firefox-nightly");
+    assert_eq!(index.icon_for(&named).as_deref(), Some("firefox-nightly"));
+
+    let chrome = launched(2, "Chromium", "google-chrome-stable");
+    assert_eq!(index.icon_for(&chrome).as_deref(), Some("google-chrome"));
+
+    let flatpak = flatpak_stream(3, "Game", "org.example.Game");
+    assert_eq!(index.icon_for(&flatpak).as_deref(), Some("org.example.Game"));
+
+    assert_eq!(index.icon_for(&stream(4, "Unknown", 1.0, 0)), None);
+}
+
+fn keys(vars: &[(&str, &str)]) -> Vec<String> {
+    let vars: HashMap<&str, &str> = vars.iter().copied().collect();
+    locale_keys(|name| vars.get(name).map(|v| v.to_string()))
+}
+
+#[test]
+fn test_locale_keys() {
+    assert_eq!(keys(&[("LANG", "de_DE.UTF-8")]), vec!["de_DE", "de"]);
+    assert_eq!(keys(&[("LANG", "sr_RS.UTF-8@latin")]), vec!["sr_RS@latin", "sr_RS", "sr@latin", "sr"]);
+    // LC_ALL over LC_MESSAGES over LANG
+    assert_eq!(keys(&[("LANG", "de_DE.UTF-8"), ("LC_MESSAGES", "fr_FR.UTF-8")]), vec!["fr_FR", "fr"]);
+    assert_eq!(keys(&[("LC_ALL", "ja_JP.UTF-8"), ("LC_MESSAGES", "fr_FR.UTF-8")]), vec!["ja_JP", "ja"]);
+    // LANGUAGE lists several, but not under the C locale
+    assert_eq!(
+        keys(&[("LANG", "en_US.UTF-8"), ("LANGUAGE", "pt_BR:pt:en")]),
+        vec!["pt_BR", "pt", "en"]
+    );
+    assert!(keys(&[("LANG", "C.UTF-8"), ("LANGUAGE", "de")]).is_empty());
+    assert!(keys(&[("LANGUAGE", "de")]).is_empty());
+    assert!(keys(&[("LANG", "")]).is_empty());
+}
+
+#[test]
+fn test_localized_name() {
+    let e = entry(
+        "firefox",
+        "Name=Firefox Web Browser\nName[de]=Firefox-Webbrowser\nName[pt_BR]=Navegador Firefox\nName[sr@latin]=Firefoks\n",
+    );
+    let name = |vars: &[(&str, &str)]| e.name_for(&keys(vars)).to_string();
+    assert_eq!(name(&[("LANG", "de_AT.UTF-8")]), "Firefox-Webbrowser");
+    assert_eq!(name(&[("LANG", "pt_BR.UTF-8")]), "Navegador Firefox");
+    assert_eq!(name(&[("LANG", "pt_PT.UTF-8")]), "Firefox Web Browser");
+    assert_eq!(name(&[("LANG", "sr_RS@latin")]), "Firefoks");
+    assert_eq!(name(&[("LANG", "C")]), "Firefox Web Browser");
+    assert_eq!(name(&[("LANG", "en_US.UTF-8"), ("LANGUAGE", "fr:de")]), "Firefox-Webbrowser");
+}
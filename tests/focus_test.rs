@@ -0,0 +1,42 @@
+mod common;
+
+use common::{stream, StreamBuilder};
+use wlvolctl::focus::{parse_hyprland, parse_sway, FocusedWindow};
+
+#[test]
+fn test_parse_hyprland() {
+    let json = r#"{"address": "0x5d3c1f20", "class": "firefox", "title": "Mozilla Firefox", "pid": 4242}"#;
+    assert_eq!(parse_hyprland(json), Some(FocusedWindow { app_id: Some("firefox".into()), pid: Some(4242) }));
+    assert_eq!(parse_hyprland("{}"), None);
+    assert_eq!(parse_hyprland("not json"), None);
+}
+
+#[test]
+fn test_parse_sway() {
+    let json = r#"{"type": "root", "focused": false, "nodes": [
+        {"type": "output", "focused": false, "nodes": [
+            {"type": "workspace", "focused": false, "nodes": [
+                {"type": "con", "focused": false, "app_id": "foot", "pid": 10, "nodes": []}
+            ], "floating_nodes": [
+                {"type": "con", "focused": true, "app_id": null, "pid": 11,
+                 "window_properties": {"class": "Steam"}, "nodes": []}
+            ]}
+        ]}
+    ]}"#;
+    assert_eq!(parse_sway(json), Some(FocusedWindow { app_id: Some("Steam".into()), pid: Some(11) }));
+
+    // an empty workspace has the focus
+    let json = r#"{"focused": false, "nodes": [{"type": "workspace", "focused": true, "nodes": []}]}"#;
+    assert_eq!(parse_sway(json), None);
+}
+
+#[test]
+fn test_owns() {
+    let firefox = FocusedWindow { app_id: Some("org.mozilla.firefox".into()), pid: None };
+    assert!(firefox.owns(&stream(1, "Firefox", 1.0, 0).with_app_id("org.mozilla.firefox")));
+    assert!(!firefox.owns(&stream(2, "Firefox", 1.0, 0)));
+
+    let by_pid = FocusedWindow { app_id: None, pid: Some(77) };
+    assert!(by_pid.owns(&stream(3, "Game", 1.0, 0).with_pid(77)));
+    assert!(!by_pid.owns(&stream(4, "Game", 1.0, 0)));
+}
@@ -0,0 +1,34 @@
+mod common;
+
+use common::{flatpak_stream, stream, StreamBuilder};
+use wlvolctl::groups::{group_streams, AppIdentity};
+
+#[test]
+fn test_group_streams() {
+    let groups = group_streams(vec![
+        stream(1, "Firefox", 0.5, 0),
+        stream(2, "mpv", 1.0, 0),
+        stream(3, "firefox", 0.25, 0),
+        stream(4, "Chromium", 1.0, 0).with_binary("slack"),
+        flatpak_stream(5, "Firefox", "org.mozilla.firefox"),
+    ]);
+
+    let ids: Vec<Vec<u32>> = groups.iter().map(|g| g.ids()).collect();
+    assert_eq!(ids, vec![vec![1, 3], vec![2], vec![4], vec![5]]);
+    assert_eq!(groups[0].first().name, "Firefox");
+    assert_eq!(groups[3].identity, AppIdentity::Flatpak("org.mozilla.firefox".into()));
+}
+
+#[test]
+fn test_group_volume_and_mute() {
+    let groups = group_streams(vec![stream(1, "Firefox", 0.5, 0), stream(2, "Firefox", 0.25, 0).muted()]);
+    let g = &groups[0];
+
+    assert_eq!(g.volume_01(), 0.5);
+    assert!(!g.mute());
+    assert_eq!(g.scaled(1.0), vec![(1, 1.0), (2, 0.5)]);
+    assert_eq!(g.scaled(0.2), vec![(1, 0.2), (2, 0.1)]);
+
+    let silent = group_streams(vec![stream(1, "mpv", 0.0, 0), stream(2, "mpv", 0.0, 0)]);
+    assert_eq!(silent[0].scaled(0.3), vec![(1, 0.3), (2, 0.3)]);
+}
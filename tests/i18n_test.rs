@@ -0,0 +1,127 @@
+use std::fs;
+
+use regex::Regex;
+use wlvolctl::cli::USAGE;
+use wlvolctl::i18n::{catalog_for, format, Catalog, CatalogError, PluralRule};
+
+const PO: &str = r#"
+msgid ""
+msgstr ""
+"Language: pl\n"
+"Plural-Forms: nplurals=3; plural=(n==1 ? 0 : n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);\n"
+
+# a translator comment
+#: src/ui.rs:12
+msgid "Mute"
+msgstr "Wycisz"
+
+msgid ""
+"two "
+"lines\n"
+msgstr "dwie\tlinie \"\\\"\n"
+
+#, fuzzy
+msgid "Lock"
+msgstr "Zablokuj"
+
+msgid "Pin"
+msgstr ""
+
+msgctxt "verb"
+msgid "Save"
+msgstr "Zapisz"
+
+msgid "{} stream"
+msgid_plural "{} streams"
+msgstr[0] "{} strumień"
+msgstr[1] "{} strumienie"
+msgstr[2] "{} strumieni"
+"#;
+
+#[test]
+fn test_plural_rule() {
+    let english = PluralRule::default();
+    assert_eq!((english.index(0), english.index(1), english.index(2)), (1, 0, 1));
+
+    let polish = Catalog::parse(PO).unwrap().plural_rule().clone();
+    assert_eq!(polish.nplurals(), 3);
+    let forms: Vec<usize> = [1, 2, 4, 5, 12, 21, 22, 112, 124].iter().map(|&n| polish.index(n)).collect();
+    assert_eq!(forms, vec![0, 1, 1, 2, 2, 2, 1, 2, 1]);
+
+    let one_form = PluralRule::parse("nplurals=1; plural=0;").unwrap();
+    assert_eq!(one_form.index(7), 0);
+    // out of range and division by zero fall back to the first form
+    assert_eq!(PluralRule::parse("nplurals=2; plural=n+5;").unwrap().index(1), 0);
+    assert_eq!(PluralRule::parse("nplurals=2; plural=!(1/n);").unwrap().index(0), 0);
+
+    for bad in ["plural=n!=1;", "nplurals=2; plural=n !! 1;", "nplurals=2; plural=(n != 1;", "nplurals=2; plural=n ? 1;"] {
+        assert_eq!(PluralRule::parse(bad), Err(CatalogError::PluralForms(bad.to_string())));
+    }
+}
+
+#[test]
+fn test_parse_catalog() {
+    let catalog = Catalog::parse(PO).unwrap();
+    assert_eq!(catalog.gettext("Mute"), "Wycisz");
+    assert_eq!(catalog.gettext("two lines\n"), "dwie\tlinie \"\\\"\n");
+    // fuzzy and untranslated messages stay in English
+    assert_eq!(catalog.gettext("Lock"), "Lock");
+    assert_eq!(catalog.gettext("Pin"), "Pin");
+    assert_eq!(catalog.gettext("Save"), "Save");
+    assert_eq!(catalog.gettext("verb\u{4}Save"), "Zapisz");
+    assert_eq!(catalog.len(), 4);
+
+    assert_eq!(catalog.ngettext("{} stream", "{} streams", 1), "{} strumień");
+    assert_eq!(catalog.ngettext("{} stream", "{} streams", 3), "{} strumienie");
+    assert_eq!(catalog.ngettext("{} stream", "{} streams", 5), "{} strumieni");
+    assert_eq!(catalog.ngettext("{} device", "{} devices", 1), "{} device");
+    assert_eq!(catalog.ngettext("{} device", "{} devices", 5), "{} devices");
+
+    assert_eq!(Catalog::parse("msgid \"a\"\nmsgstr[1] \"b\"\n").unwrap_err(), CatalogError::Syntax(2, "unexpected msgstr[1]".into()));
+    assert!(matches!(Catalog::parse("msgid \"a\nmsgstr \"b\"\n"), Err(CatalogError::Syntax(1, _))));
+    assert!(matches!(Catalog::parse("\"stray\"\n"), Err(CatalogError::Syntax(1, _))));
+}
+
+#[test]
+fn test_format() {
+    assert_eq!(format("Saved scene {} ({}, {})", &[&"jazz", &"2 streams", &1]), "Saved scene jazz (2 streams, 1)");
+    assert_eq!(format("{1} von {0}", &[&"a", &"b"]), "b von a");
+    assert_eq!(format("{{}} {}", &[&5]), "{} 5");
+    // a placeholder without an argument is left as is
+    assert_eq!(format("{} and {}", &[&1]), "1 and {}");
+    assert_eq!(format("{x} {}", &[&1]), "{x} 1");
+}
+
+// Every message marked with tr! or trn! in the sources, and the usage text.
+fn marked_messages() -> Vec<String> {
+    let call = Regex::new(r#"\btrn?!\(\s*"((?:[^"\\]|\\.)*)"(?:,\s*"((?:[^"\\]|\\.)*)")?"#).unwrap();
+    let unescape = |s: &str| s.replace("\\\"", "\"").replace("\\n", "\n").replace("\\\\", "\\");
+    let mut messages = vec![USAGE.to_string()];
+    for file in fs::read_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/src")).unwrap() {
+        let path = file.unwrap().path();
+        if path.extension().and_then(|e| e.to_str()) != Some("rs") {
+            continue;
+        }
+        let text = fs::read_to_string(&path).unwrap();
+        let code = text.lines().filter(|l| !l.trim_start().starts_with("//")).collect::<Vec<_>>().join("\n");
+        messages.extend(call.captures_iter(&code).map(|c| unescape(&c[1])));
+    }
+    messages.sort();
+    messages.dedup();
+    messages
+}
+
+#[test]
+fn test_german_is_complete() {
+    let german = catalog_for(&["de_DE".into(), "de".into()]).unwrap();
+    let messages = marked_messages();
+    assert!(messages.len() > 40);
+    let missing: Vec<&String> = messages.iter().filter(|m| !german.contains(m)).collect();
+    assert!(missing.is_empty(), "untranslated: {:?}", missing);
+    assert_eq!(german.len(), messages.len());
+
+    assert_eq!(german.gettext("Mute"), "Stumm");
+    assert_eq!(german.ngettext("{} stream", "{} streams", 1), "{} Stream");
+    assert!(german.gettext(USAGE).starts_with("Aufruf: wlvolctl"));
+    assert!(catalog_for(&["fr".into()]).is_none());
+}
@@ -0,0 +1,90 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use wlvolctl::icons::IconResolver;
+
+fn write(path: &Path, text: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, text).unwrap();
+}
+
+// ~/.icons, /usr/share/icons and /usr/share/pixmaps in miniature.
+fn fixture(test: &str) -> (PathBuf, Vec<PathBuf>) {
+    let root = std::env::temp_dir().join(format!("wlvolctl-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&root);
+    let (user, system, pixmaps) = (root.join("user"), root.join("system"), root.join("pixmaps"));
+
+    write(
+        &user.join("Custom/index.theme"),
+        "[Icon Theme]\nName=Custom\nInherits=Adwaita\nDirectories=48x48/apps\n\n[48x48/apps]\nSize=48\nType=Fixed\n",
+    );
+    write(&user.join("Custom/48x48/apps/firefox.png"), "");
+
+    write(
+        &system.join("Adwaita/index.theme"),
+        "[Icon Theme]\nName=Adwaita\nInherits=hicolor,Custom\nDirectories=16x16/apps,scalable/apps\n\
+         ScaledDirectories=32x32@2/apps\n\n\
+         [16x16/apps]\nSize=16\nType=Fixed\n\n\
+         [32x32@2/apps]\nSize=32\nScale=2\nType=Fixed\n\n\
+         [scalable/apps]\nSize=128\nMinSize=8\nMaxSize=512\nType=Scalable\n",
+    );
+    write(&system.join("Adwaita/16x16/apps/tiny.png"), "");
+    write(&system.join("Adwaita/16x16/apps/spotify.png"), "");
+    write(&system.join("Adwaita/scalable/apps/spotify.svg"), "");
+    write(&system.join("Adwaita/32x32@2/apps/steam.png"), "");
+
+    write(
+        &system.join("hicolor/index.theme"),
+        "[Icon Theme]\nName=Hicolor\nDirectories=48x48/apps,256x256/apps\n\n\
+         [48x48/apps]\nSize=48\n\n[256x256/apps]\nSize=256\n",
+    );
+    write(&system.join("hicolor/48x48/apps/firefox.png"), "");
+    write(&system.join("hicolor/48x48/apps/mpv.png"), "");
+    write(&system.join("hicolor/256x256/apps/steam.png"), "");
+
+    write(&pixmaps.join("xterm.xpm"), "");
+
+    (root, vec![user, system, pixmaps])
+}
+
+#[test]
+fn test_theme_chain() {
+    let (root, dirs) = fixture("icons-chain");
+    let resolver = IconResolver::with_base_dirs("Custom", dirs.clone());
+    assert_eq!(resolver.theme_chain(), vec!["Custom", "Adwaita", "hicolor"]);
+
+    // a missing theme still falls back to hicolor
+    let resolver = IconResolver::with_base_dirs("Missing", dirs);
+    assert_eq!(resolver.theme_chain(), vec!["hicolor"]);
+    fs::remove_dir_all(root).unwrap();
+}
+
+#[test]
+fn test_lookup() {
+    let (root, dirs) = fixture("icons-lookup");
+    let (user, system, pixmaps) = (&dirs[0], &dirs[1], &dirs[2]);
+    let resolver = IconResolver::with_base_dirs("Custom", dirs.clone());
+    let lookup = |name: &str, size: i32, scale: i32| resolver.lookup(name, size, scale);
+
+    // the selected theme wins over hicolor
+    assert_eq!(lookup("firefox", 48, 1), Some(user.join("Custom/48x48/apps/firefox.png")));
+    // inherited, in a directory matching the size
+    assert_eq!(lookup("spotify", 48, 1), Some(system.join("Adwaita/scalable/apps/spotify.svg")));
+    assert_eq!(lookup("spotify", 16, 1), Some(system.join("Adwaita/16x16/apps/spotify.png")));
+    // no match in size: the closest one in the first theme that has it
+    assert_eq!(lookup("tiny", 48, 1), Some(system.join("Adwaita/16x16/apps/tiny.png")));
+    // scaled directories only match their scale
+    assert_eq!(lookup("steam", 32, 2), Some(system.join("Adwaita/32x32@2/apps/steam.png")));
+    assert_eq!(lookup("steam", 48, 1), Some(system.join("Adwaita/32x32@2/apps/steam.png")));
+    // from hicolor, with a stray extension
+    assert_eq!(lookup("mpv.png", 48, 1), Some(system.join("hicolor/48x48/apps/mpv.png")));
+    // pixmaps fallback
+    assert_eq!(lookup("xterm", 48, 1), Some(pixmaps.join("xterm.xpm")));
+
+    let absolute = pixmaps.join("xterm.xpm");
+    assert_eq!(lookup(absolute.to_str().unwrap(), 48, 1), Some(absolute.clone()));
+    assert_eq!(lookup("/nonexistent/icon.png", 48, 1), None);
+    assert_eq!(lookup("nothing", 48, 1), None);
+    assert_eq!(lookup("../escape", 48, 1), None);
+    fs::remove_dir_all(root).unwrap();
+}
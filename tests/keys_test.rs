@@ -0,0 +1,47 @@
+use wlvolctl::keys::{parse_chords, Action, Chord, KeyBindings, ACTION_NAMES};
+
+#[test]
+fn test_action_names() {
+    for name in ACTION_NAMES {
+        assert!(Action::from_name(name).is_some(), "{}", name);
+    }
+    assert_eq!(Action::from_name("jump_7"), Some(Action::Jump(7)));
+    assert_eq!(Action::from_name("jump_10"), None);
+    assert_eq!(Action::from_name("louder"), None);
+}
+
+#[test]
+fn test_parse_chords() {
+    assert_eq!(parse_chords("Up, k").unwrap(), vec![Chord::parse("Up").unwrap(), Chord::parse("k").unwrap()]);
+    assert_eq!(Chord::parse("ctrl + m").unwrap(), Chord::parse("Ctrl+m").unwrap());
+    assert!(parse_chords("").unwrap().is_empty());
+    assert!(Chord::parse("Shift+m").is_err());
+    assert!(Chord::parse("Ctrl+").is_err());
+    assert!(Chord::parse("Page Up").is_err());
+}
+
+#[test]
+fn test_default_bindings() {
+    let keys = KeyBindings::default();
+    assert_eq!(keys.action_for("Left", false, false), Some(Action::Previous));
+    assert_eq!(keys.action_for("Page_Down", false, false), Some(Action::PageDown));
+    assert_eq!(keys.action_for("m", false, false), Some(Action::ToggleMute));
+    assert_eq!(keys.action_for("3", false, false), Some(Action::Jump(3)));
+    assert_eq!(keys.action_for("KP_0", false, false), Some(Action::Jump(0)));
+    assert_eq!(keys.action_for("Escape", false, false), Some(Action::Close));
+    // modifiers have to match
+    assert_eq!(keys.action_for("m", true, false), None);
+    assert_eq!(keys.action_for("M", false, false), None);
+}
+
+#[test]
+fn test_bind() {
+    let mut keys = KeyBindings::default();
+    keys.bind(Action::VolumeUp, parse_chords("k, Ctrl+Up").unwrap());
+    keys.bind(Action::Close, Vec::new());
+    assert_eq!(keys.action_for("k", false, false), Some(Action::VolumeUp));
+    assert_eq!(keys.action_for("Up", true, false), Some(Action::VolumeUp));
+    assert_eq!(keys.action_for("Up", false, false), None);
+    assert_eq!(keys.action_for("Escape", false, false), None);
+    assert_eq!(keys.keys_for(Action::VolumeUp).len(), 2);
+}
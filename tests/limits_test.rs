@@ -0,0 +1,74 @@
+mod common;
+
+use common::{stream, MockBackend, StreamBuilder, VanishingBackend};
+use wlvolctl::audio::AudioBackend;
+use wlvolctl::limits::{CappedBackend, Limits, LimitsError};
+
+#[test]
+fn test_parse_and_cap_for() {
+    let limits = Limits::parse("[limits]\nglobal = 90%\n\n[apps]\nFirefox = 60%\nsteam = 95%\n").unwrap();
+    assert_eq!(limits.global, Some(0.9));
+
+    assert_eq!(limits.cap_for(&stream(1, "firefox", 1.0, 0)), Some(0.6));
+    // the global cap is stricter than the app cap
+    assert_eq!(limits.cap_for(&stream(2, "Steam", 1.0, 0)), Some(0.9));
+
+    let by_binary = stream(3, "Some Game", 1.0, 0).with_binary("firefox");
+    assert_eq!(limits.cap_for(&by_binary), Some(0.6));
+
+    assert_eq!(Limits::default().cap_for(&by_binary), None);
+    assert!(matches!(Limits::parse("[limits]\nglobal = lots\n"), Err(LimitsError::Parse(_))));
+}
+
+#[test]
+fn test_capped_backend_clamps_requests() {
+    let limits = Limits::parse("[apps]\nmpv = 50%\n").unwrap();
+    let backend = CappedBackend::new(MockBackend::new(), limits);
+
+    backend.set_volume(2, 0.9).unwrap();
+    assert!((backend.inner().list_streams().unwrap()[1].volume_01 - 0.5).abs() < 0.001);
+
+    backend.set_volume(2, 0.3).unwrap();
+    assert!((backend.inner().list_streams().unwrap()[1].volume_01 - 0.3).abs() < 0.001);
+
+    // uncapped app
+    backend.set_volume(1, 1.0).unwrap();
+    assert!((backend.inner().list_streams().unwrap()[0].volume_01 - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn test_capped_backend_pulls_external_changes_down() {
+    let limits = Limits::parse("[limits]\nglobal = 70%\n").unwrap();
+    let backend = CappedBackend::new(MockBackend::new(), limits);
+
+    // the app raised itself to 100% behind our back
+    backend.inner().set_volume(2, 1.0).unwrap();
+
+    let streams = backend.list_streams().unwrap();
+    assert!((streams[1].volume_01 - 0.7).abs() < 0.001);
+    assert!((backend.inner().list_streams().unwrap()[1].volume_01 - 0.7).abs() < 0.001);
+}
+
+#[test]
+fn test_pull_down_failure_keeps_listing() {
+    let limits = Limits::parse("[limits]\nglobal = 70%\n").unwrap();
+    let backend = CappedBackend::new(VanishingBackend::new(1), limits);
+    backend.inner().inner.set_volume(2, 1.0).unwrap();
+
+    let streams = backend.list_streams().unwrap();
+    assert_eq!(streams.len(), 2);
+    // Firefox could not be pulled down and shows what the server had
+    assert!((streams[0].volume_01 - 0.8).abs() < 0.001);
+    assert!((streams[1].volume_01 - 0.7).abs() < 0.001);
+}
+
+#[test]
+fn test_set_limits() {
+    let backend = CappedBackend::new(MockBackend::new(), Limits::default());
+    backend.list_streams().unwrap();
+    assert!((backend.inner().list_streams().unwrap()[1].volume_01 - 1.0).abs() < 0.001);
+
+    backend.set_limits(Limits::parse("[apps]\nmpv = 40%\n").unwrap());
+    let streams = backend.list_streams().unwrap();
+    assert!((streams[1].volume_01 - 0.4).abs() < 0.001);
+}
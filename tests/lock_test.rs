@@ -0,0 +1,91 @@
+mod common;
+
+use std::fs;
+
+use common::{MockBackend, VanishingBackend};
+use wlvolctl::audio::AudioBackend;
+use wlvolctl::lock::{Change, LockingBackend, MAX_REVERTS};
+
+#[test]
+fn test_locked_stream_reverts_external_changes() {
+    let log = std::env::temp_dir().join(format!("wlvolctl-lock-{}.log", std::process::id()));
+    let _ = fs::remove_file(&log);
+    let backend = LockingBackend::with_log(MockBackend::new(), log.clone());
+
+    let firefox = backend.list_streams().unwrap()[0].clone();
+    backend.set_locked(&firefox, true);
+    assert!(backend.is_locked(firefox.id));
+
+    // Firefox raises and mutes itself
+    backend.inner().set_volume(firefox.id, 1.0).unwrap();
+    backend.inner().set_mute(firefox.id, true).unwrap();
+
+    let listed = backend.list_streams().unwrap();
+    assert!((listed[0].volume_01 - 0.8).abs() < 0.001);
+    assert!(!listed[0].mute);
+    assert!((backend.inner().list_streams().unwrap()[0].volume_01 - 0.8).abs() < 0.001);
+
+    let reverts = backend.reverts_of(&[firefox.id]);
+    assert_eq!(reverts.len(), 2);
+    assert_eq!(reverts[0].app, "Firefox");
+    assert_eq!(reverts[0].change, Change::Volume { from: 0.8, to: 1.0 });
+    assert_eq!(fs::read_to_string(&log).unwrap().lines().count(), 2);
+    let _ = fs::remove_file(&log);
+}
+
+#[test]
+fn test_own_changes_move_the_lock() {
+    let backend = LockingBackend::new(MockBackend::new());
+    let mpv = backend.list_streams().unwrap()[1].clone();
+    backend.set_locked(&mpv, true);
+
+    backend.set_volume(mpv.id, 0.4).unwrap();
+    backend.set_mute(mpv.id, true).unwrap();
+
+    let listed = backend.list_streams().unwrap();
+    assert!((listed[1].volume_01 - 0.4).abs() < 0.001);
+    assert!(listed[1].mute);
+    assert!(backend.reverts_of(&[mpv.id]).is_empty());
+
+    backend.set_locked(&mpv, false);
+    backend.inner().set_volume(mpv.id, 1.0).unwrap();
+    assert!((backend.list_streams().unwrap()[1].volume_01 - 1.0).abs() < 0.001);
+}
+
+#[test]
+fn test_reverts_keep_the_latest() {
+    let backend = LockingBackend::new(MockBackend::new());
+    let firefox = backend.list_streams().unwrap()[0].clone();
+    backend.set_locked(&firefox, true);
+
+    for i in 0..MAX_REVERTS + 10 {
+        backend.inner().set_volume(firefox.id, if i % 2 == 0 { 0.2 } else { 0.3 }).unwrap();
+        backend.list_streams().unwrap();
+    }
+    let reverts = backend.reverts_of(&[firefox.id]);
+    assert_eq!(reverts.len(), MAX_REVERTS);
+    assert_eq!(reverts.last().unwrap().change, Change::Volume { from: 0.8, to: 0.3 });
+    assert!(backend.reverts_of(&[2]).is_empty());
+}
+
+#[test]
+fn test_failed_revert_keeps_listing() {
+    let backend = LockingBackend::new(VanishingBackend::new(1));
+    let streams = backend.list_streams().unwrap();
+    backend.set_locked(&streams[0], true);
+    backend.set_locked(&streams[1], true);
+
+    // both change behind our back, then Firefox ends
+    backend.inner().inner.set_volume(1, 1.0).unwrap();
+    backend.inner().inner.set_mute(1, true).unwrap();
+    backend.inner().inner.set_volume(2, 0.2).unwrap();
+
+    let listed = backend.list_streams().unwrap();
+    assert_eq!(listed.len(), 2);
+    // Firefox shows what the server had, mpv is back at its level
+    assert!((listed[0].volume_01 - 1.0).abs() < 0.001);
+    assert!(listed[0].mute);
+    assert!((listed[1].volume_01 - 1.0).abs() < 0.001);
+    assert!(backend.reverts_of(&[1]).is_empty());
+    assert_eq!(backend.reverts_of(&[2]).len(), 1);
+}
@@ -0,0 +1,36 @@
+mod common;
+
+use common::{stream, StreamBuilder};
+use wlvolctl::memory::{MemoryError, Remembered, VolumeMemory};
+
+#[test]
+fn test_remember_and_recall() {
+    let mut memory = VolumeMemory::default();
+    let mpv = stream(2, "mpv", 0.4, 0);
+
+    assert_eq!(memory.recall(&mpv), None);
+    assert!(memory.remember(&mpv));
+    assert!(!memory.remember(&mpv));
+
+    assert!(memory.remember(&mpv.muted()));
+
+    // a new stream of the same app, whatever its id and case
+    let reopened = stream(9, "MPV", 1.0, 0);
+    assert_eq!(memory.recall(&reopened), Some(Remembered { volume_01: 0.4, mute: true }));
+}
+
+#[test]
+fn test_save_and_load() {
+    let path = std::env::temp_dir().join(format!("wlvolctl-volumes-{}", std::process::id()));
+    let mut memory = VolumeMemory::default();
+    memory.remember(&stream(1, "Firefox", 0.8, 0));
+    memory.remember(&stream(2, "mpv", 0.25, 0));
+    memory.save(&path).unwrap();
+
+    let loaded = VolumeMemory::load(&path).unwrap();
+    assert_eq!(loaded, memory);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(VolumeMemory::load(&path).unwrap(), VolumeMemory::default());
+    assert!(matches!(VolumeMemory::parse("[mpv]\nvolume = loud\n"), Err(MemoryError::Parse(_))));
+}
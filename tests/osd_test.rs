@@ -0,0 +1,37 @@
+mod common;
+
+use common::{device, stream, DeviceBuilder, StreamBuilder};
+use wlvolctl::desktop::{AppIndex, DesktopEntry};
+use wlvolctl::osd::OsdMessage;
+
+#[test]
+fn test_stream_and_device_messages() {
+    let mpv = stream(2, "mpv", 0.456, 0);
+    let msg = OsdMessage::for_stream(&mpv, None);
+    assert_eq!(msg.title, "mpv");
+    assert_eq!(msg.icon, "audio-volume-medium");
+    assert_eq!(msg.body(), "46%");
+    assert_eq!(msg.percent(), 46);
+
+    let msg = OsdMessage::for_stream(&mpv.with_icon("mpv").muted(), None);
+    assert_eq!(msg.icon, "mpv");
+    assert_eq!(msg.body(), "Muted");
+
+    let speakers = device(0, "speakers", 0.9).with_description("Built-in Speakers");
+    let msg = OsdMessage::for_device(&speakers);
+    assert_eq!(msg.title, "Built-in Speakers");
+    assert_eq!(msg.icon, "audio-volume-high");
+    assert_eq!(OsdMessage::for_device(&device(1, "headset", 0.0)).icon, "audio-volume-muted");
+}
+
+#[test]
+fn test_stream_message_uses_the_app_icon() {
+    let entry = DesktopEntry::parse("chromium.desktop", "[Desktop Entry]\nType=Application\nName=Chromium\nIcon=chromium\n");
+    let apps = AppIndex::new(vec![entry.unwrap()]);
+
+    // Chromium names no icon of its own
+    let chromium = stream(3, "Chromium", 0.5, 0);
+    assert_eq!(OsdMessage::for_stream(&chromium, Some(&apps)).icon, "chromium");
+    let unknown = stream(4, "ffplay", 0.5, 0);
+    assert_eq!(OsdMessage::for_stream(&unknown, Some(&apps)).icon, "audio-volume-medium");
+}
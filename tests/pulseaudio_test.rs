@@ -1,5 +1,5 @@
-use wlvolctl::pulseaudio_cli::PulseAudioCli;
-use wlvolctl::audio::AudioBackend;
+use wlvolctl::pulseaudio_cli::{parse_default_sink, parse_event, parse_sink_inputs, parse_sinks, PulseAudioCli};
+use wlvolctl::audio::{AudioBackend, BackendEvent};
 
 #[test]
 fn test_list_streams_pulseaudio() {
@@ -97,3 +97,97 @@ fn test_volume_and_mute_state_updates() {
     println!("Restored original state for {}", s.name);
 }
 
+const SINK_INPUTS: &str = r#"Sink Input #42
+	Driver: protocol-native.c
+	Owner Module: 10
+	Client: 57
+	Sink: 1
+	Sample Specification: float32le 2ch 48000Hz
+	Channel Map: front-left,front-right
+	Format: pcm, format.sample_format = "\"float32le\""  format.rate = "48000"
+	Corked: no
+	Mute: yes
+	Volume: front-left: 45875 /  70% / -9.29 dB,   front-right: 45875 /  70% / -9.29 dB
+	        balance 0.00
+	Properties:
+		media.name = "50% off - YouTube"
+		application.name = "Firefox"
+		application.process.id = "4242"
+		application.process.binary = "firefox"
+		application.icon_name = "firefox"
+		pipewire.access.portal.app_id = "org.mozilla.firefox"
+		media.role = "video"
+Sink Input #43
+	Sink: 0
+	Mute: no
+	Volume: mono: 65536 / 100% / 0.00 dB
+	Properties:
+		application.name = "mpv"
+"#;
+
+const SINKS: &str = r#"Sink #0
+	State: SUSPENDED
+	Name: alsa_output.pci-0000_00_1f.3.analog-stereo
+	Description: Built-in Audio Analog Stereo
+	Mute: no
+	Volume: front-left: 32768 /  50% / -18.06 dB,   front-right: 32768 /  50% / -18.06 dB
+	        balance 0.00
+	Base Volume: 65536 / 100% / 0.00 dB
+Sink #1
+	State: RUNNING
+	Name: alsa_output.usb-headset.analog-stereo
+	Description: USB Headset
+	Mute: yes
+	Volume: front-left: 58982 /  90% / -2.75 dB,   front-right: 58982 /  90% / -2.75 dB
+	Base Volume: 65536 / 100% / 0.00 dB
+"#;
+
+#[test]
+fn test_parse_sink_inputs() {
+    let streams = parse_sink_inputs(SINK_INPUTS);
+    assert_eq!(streams.len(), 2);
+
+    assert_eq!(streams[0].id, 42);
+    assert_eq!(streams[0].name, "Firefox");
+    assert!(streams[0].mute);
+    assert!((streams[0].volume_01 - 0.7).abs() < 0.001);
+    assert_eq!(streams[0].device_id, Some(1));
+    assert_eq!(streams[0].binary.as_deref(), Some("firefox"));
+    assert_eq!(streams[0].icon_name.as_deref(), Some("firefox"));
+    assert_eq!(streams[0].app_id.as_deref(), Some("org.mozilla.firefox"));
+    assert_eq!(streams[0].media_name.as_deref(), Some("50% off - YouTube"));
+    assert_eq!(streams[0].role.as_deref(), Some("video"));
+    assert_eq!(streams[0].pid, Some(4242));
+
+    assert_eq!(streams[1].name, "mpv");
+    assert_eq!(streams[1].device_id, Some(0));
+}
+
+#[test]
+fn test_parse_sinks() {
+    let devices = parse_sinks(SINKS);
+    assert_eq!(devices.len(), 2);
+
+    assert_eq!(devices[0].name, "alsa_output.pci-0000_00_1f.3.analog-stereo");
+    assert_eq!(devices[0].description, "Built-in Audio Analog Stereo");
+    assert!((devices[0].volume_01 - 0.5).abs() < 0.001);
+    assert!(!devices[0].mute);
+
+    assert_eq!(devices[1].id, 1);
+    assert!(devices[1].mute);
+    assert!((devices[1].volume_01 - 0.9).abs() < 0.001);
+}
+
+#[test]
+fn test_parse_events() {
+    assert_eq!(parse_event("Event 'new' on sink-input #57"), Some(BackendEvent::StreamAdded(57)));
+    assert_eq!(parse_event("Event 'remove' on sink-input #57"), Some(BackendEvent::StreamRemoved(57)));
+    assert_eq!(parse_event("Event 'change' on sink-input #57"), Some(BackendEvent::StreamChanged(57)));
+    assert_eq!(parse_event("Event 'change' on sink #0"), Some(BackendEvent::DeviceChanged(0)));
+    assert_eq!(parse_event("Event 'change' on server #4294967295"), Some(BackendEvent::ServerChanged));
+    assert_eq!(parse_event("Event 'new' on client #12"), None);
+    assert_eq!(parse_event("garbage"), None);
+
+    let info = "Server Name: PulseAudio (on PipeWire 1.0.5)\nDefault Sink: alsa_output.usb-headset\nDefault Source: x\n";
+    assert_eq!(parse_default_sink(info).as_deref(), Some("alsa_output.usb-headset"));
+}
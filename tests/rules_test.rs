@@ -0,0 +1,76 @@
+mod common;
+
+use common::{event_sound, stream, MockBackend, StreamBuilder};
+use wlvolctl::audio::AudioBackend;
+use wlvolctl::rules::{RuleEngine, RuleError, RuleSet};
+
+const RULES: &str = r#"
+[chrome events start muted]
+binary = chrom*
+role = event
+mute = yes
+
+[steam games on headset]
+binary = steam_app_*
+volume = 60%
+device = *HEAD*
+
+[no notification column]
+role = event
+hide = yes
+"#;
+
+#[test]
+fn test_actions_for_matching_streams() {
+    let rules = RuleSet::parse(RULES).unwrap();
+    assert_eq!(rules.rules().len(), 3);
+
+    let ding = event_sound(1, "Chromium").with_binary("chromium-browser");
+    let actions = rules.actions_for(&ding);
+    assert_eq!(actions.mute, Some(true));
+    assert!(actions.hide);
+    assert_eq!(actions.volume, None);
+
+    // missing property never matches
+    let plain = stream(2, "Chromium", 1.0, 0);
+    assert_eq!(rules.actions_for(&plain), Default::default());
+}
+
+#[test]
+fn test_engine_applies_only_to_new_streams() {
+    let backend = MockBackend::new();
+    let rules = RuleSet::parse("[quiet mpv]\nname = MPV\nvolume = 20%\ndevice = head*\n").unwrap();
+    let mut engine = RuleEngine::new(rules);
+
+    // already running when wlvolctl starts: left alone
+    engine.process(&backend.list_streams().unwrap(), &backend);
+    assert!((backend.list_streams().unwrap()[1].volume_01 - 1.0).abs() < 0.001);
+
+    backend.streams.borrow_mut().push(stream(7, "mpv", 1.0, 0).with_binary("mpv"));
+    engine.process(&backend.list_streams().unwrap(), &backend);
+
+    let streams = backend.list_streams().unwrap();
+    assert!((streams[2].volume_01 - 0.2).abs() < 0.001);
+    assert_eq!(streams[2].device_id, Some(1));
+
+    // seen once, not re-applied on later refreshes
+    backend.set_volume(7, 0.9).unwrap();
+    engine.process(&backend.list_streams().unwrap(), &backend);
+    assert!((backend.list_streams().unwrap()[2].volume_01 - 0.9).abs() < 0.001);
+}
+
+#[test]
+fn test_hidden_streams() {
+    let backend = MockBackend::new();
+    let mut engine = RuleEngine::new(RuleSet::parse("[hide firefox]\nname = firefox\nhide = yes\n").unwrap());
+    engine.process(&backend.list_streams().unwrap(), &backend);
+    assert!(engine.is_hidden(1));
+    assert!(!engine.is_hidden(2));
+}
+
+#[test]
+fn test_invalid_rules() {
+    assert!(matches!(RuleSet::parse("[x]\nvolume = 10%\n"), Err(RuleError::Invalid { .. })));
+    assert!(matches!(RuleSet::parse("[x]\nname = a\nvolume = loud\n"), Err(RuleError::Invalid { .. })));
+    assert!(matches!(RuleSet::parse("[x]\nname = a\ncolour = red\n"), Err(RuleError::Invalid { .. })));
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use common::{MockBackend, VanishingBackend};
+use wlvolctl::audio::AudioBackend;
+use wlvolctl::scenes::{Scene, SceneError, SceneStore};
+
+fn temp_store(test: &str) -> SceneStore {
+    let dir: PathBuf = std::env::temp_dir().join(format!("wlvolctl-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    SceneStore::new(dir)
+}
+
+#[test]
+fn test_capture_save_load_roundtrip() {
+    let backend = MockBackend::new();
+    let store = temp_store("scenes-roundtrip");
+
+    let scene = Scene::capture("meeting", &backend).unwrap();
+    assert_eq!(scene.streams.len(), 2);
+    assert_eq!(scene.devices.len(), 2);
+    assert_eq!(scene.streams[0].device.as_deref(), Some("speakers"));
+
+    store.save(&scene).unwrap();
+    assert_eq!(store.list().unwrap(), vec!["meeting".to_string()]);
+    assert_eq!(store.load("meeting").unwrap(), scene);
+
+    store.delete("meeting").unwrap();
+    assert!(store.list().unwrap().is_empty());
+    assert!(matches!(store.load("meeting"), Err(SceneError::NotFound(_))));
+}
+
+#[test]
+fn test_apply_restores_volume_mute_and_routing() {
+    let backend = MockBackend::new();
+    let scene = Scene::capture("focus", &backend).unwrap();
+
+    backend.set_volume(1, 0.1).unwrap();
+    backend.set_mute(2, true).unwrap();
+    backend.move_stream(1, 1).unwrap();
+    backend.set_device_volume(0, 1.0).unwrap();
+
+    scene.apply(&backend, Duration::from_millis(120)).unwrap();
+
+    let streams = backend.list_streams().unwrap();
+    assert!((streams[0].volume_01 - 0.8).abs() < 0.001);
+    assert_eq!(streams[0].device_id, Some(0));
+    assert!(!streams[1].mute);
+    assert!((backend.list_devices().unwrap()[0].volume_01 - 0.5).abs() < 0.001);
+}
+
+#[test]
+fn test_apply_skips_a_stream_that_ended() {
+    let backend = VanishingBackend::new(1);
+    let mut scene = Scene::capture("quiet", &backend).unwrap();
+    for entry in &mut scene.streams {
+        entry.volume_01 = 0.2;
+        entry.mute = true;
+    }
+    scene.streams[0].device = Some("headset".into());
+    scene.devices[0].volume_01 = 0.1;
+
+    scene.apply(&backend, Duration::from_millis(120)).unwrap();
+
+    let streams = backend.list_streams().unwrap();
+    // Firefox is left as it was; mpv and the speakers still get the scene
+    assert!((streams[0].volume_01 - 0.8).abs() < 0.001);
+    assert!(!streams[0].mute);
+    assert!((streams[1].volume_01 - 0.2).abs() < 0.001);
+    assert!(streams[1].mute);
+    assert!((backend.list_devices().unwrap()[0].volume_01 - 0.1).abs() < 0.001);
+}
+
+#[test]
+fn test_hand_written_scene_file() {
+    let store = temp_store("scenes-handwritten");
+    fs::create_dir_all(store.dir()).unwrap();
+    fs::write(
+        store.dir().join("gaming.ini"),
+        "[stream mpv]\nvolume = 30%\nmute = yes\n\n[device headset]\nvolume = 75\n",
+    )
+    .unwrap();
+
+    let scene = store.load("gaming").unwrap();
+    assert!(scene.streams[0].mute);
+    assert!((scene.streams[0].volume_01 - 0.3).abs() < 0.001);
+    assert!((scene.devices[0].volume_01 - 0.75).abs() < 0.001);
+
+    fs::write(store.dir().join("broken.ini"), "[stream mpv]\nvolume = loud\n").unwrap();
+    assert!(matches!(store.load("broken"), Err(SceneError::Parse { .. })));
+    assert!(matches!(store.load("../etc"), Err(SceneError::InvalidName(_))));
+}
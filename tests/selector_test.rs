@@ -0,0 +1,80 @@
+mod common;
+
+use common::{device, event_sound, stream, StreamBuilder};
+use wlvolctl::audio::Stream;
+use wlvolctl::focus::FocusedWindow;
+use wlvolctl::selector::{Scope, Selector};
+
+fn streams() -> Vec<Stream> {
+    vec![
+        stream(10, "Firefox", 0.5, 0).with_binary("firefox").with_role("video").with_pid(1234),
+        event_sound(11, "Firefox").with_binary("firefox"),
+        stream(12, "mpv", 0.8, 1).with_binary("mpv").with_role("music").with_app_id("io.mpv.Mpv"),
+    ]
+}
+
+fn ids_in(expr: &str, focused: Option<FocusedWindow>) -> Vec<u32> {
+    let devices = vec![device(0, "alsa_output.pci-0000_01_00.1.hdmi-stereo", 1.0), device(1, "headset", 1.0)];
+    let scope = Scope { devices, focused };
+    Selector::parse(expr).unwrap().select(&streams(), &scope).iter().map(|s| s.id).collect()
+}
+
+fn ids(expr: &str) -> Vec<u32> {
+    ids_in(expr, None)
+}
+
+fn window(app_id: &str) -> Option<FocusedWindow> {
+    Some(FocusedWindow { app_id: Some(app_id.into()), pid: None })
+}
+
+#[test]
+fn test_simple_terms() {
+    assert_eq!(ids("all"), vec![10, 11, 12]);
+    assert_eq!(ids("12"), vec![12]);
+    assert_eq!(ids("firefox"), vec![10, 11]);
+    assert_eq!(ids("name~=fire"), vec![10, 11]);
+    assert_eq!(ids("binary=MPV"), vec![12]);
+    assert_eq!(ids("role:music"), vec![12]);
+    assert_eq!(ids("pid=1234"), vec![10]);
+    assert_eq!(ids("flatpak=io.mpv.*"), vec![12]);
+    assert_eq!(ids("device=hdmi"), vec![10, 11]);
+}
+
+#[test]
+fn test_negation_and_combination() {
+    assert_eq!(ids("!role=event"), vec![10, 12]);
+    assert_eq!(ids("binary=firefox,!role:event"), vec![10]);
+    assert_eq!(ids("role=event|binary=mpv"), vec![11, 12]);
+    // a missing property never equals anything
+    assert_eq!(ids("media=*"), Vec::<u32>::new());
+}
+
+#[test]
+fn test_focused_role() {
+    // the focused app's notification sounds are left out, others' are not
+    assert_eq!(ids_in("!focused-role:event", window("firefox")), vec![10, 12]);
+    assert_eq!(ids_in("!focused-role:event", window("mpv")), vec![10, 11, 12]);
+    assert_eq!(ids_in("focused-role=event", window("Firefox")), vec![11]);
+    assert_eq!(ids_in("focused-role=music", window("io.mpv.Mpv")), vec![12]);
+    let by_pid = Some(FocusedWindow { app_id: None, pid: Some(1234) });
+    assert_eq!(ids_in("focused-role=video", by_pid), vec![10]);
+    // without a known focus nothing is the focused app's
+    assert_eq!(ids("!focused-role:event"), vec![10, 11, 12]);
+}
+
+#[test]
+fn test_needs_devices() {
+    assert!(Selector::parse("binary=mpv|device=usb").unwrap().needs_devices());
+    assert!(!Selector::parse("binary=mpv").unwrap().needs_devices());
+    assert!(Selector::parse("!focused-role:event").unwrap().needs_focus());
+    assert!(!Selector::parse("role=event").unwrap().needs_focus());
+}
+
+#[test]
+fn test_parse_errors() {
+    assert!(Selector::parse("colour=red").is_err());
+    assert!(Selector::parse("pid=abc").is_err());
+    assert!(Selector::parse("name=").is_err());
+    assert!(Selector::parse("binary=mpv,,role=music").is_err());
+    assert!(Selector::parse("name~=(").is_err());
+}
@@ -0,0 +1,93 @@
+mod common;
+
+use std::sync::mpsc::RecvTimeoutError;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use common::{stream, StreamBuilder};
+use wlvolctl::audio::{AudioBackend, AudioError, Stream};
+use wlvolctl::rules::{RuleEngine, RuleSet};
+use wlvolctl::worker::{BackendWorker, Reply, Request, RequestQueue};
+
+const WAIT: Duration = Duration::from_secs(5);
+
+/// Like the common mock, but shareable with the worker thread.
+struct SharedBackend {
+    streams: Mutex<Vec<Stream>>,
+}
+
+impl AudioBackend for SharedBackend {
+    fn list_streams(&self) -> Result<Vec<Stream>, AudioError> {
+        Ok(self.streams.lock().unwrap().clone())
+    }
+
+    fn set_volume(&self, stream_id: u32, vol_01: f32) -> Result<(), AudioError> {
+        let mut streams = self.streams.lock().unwrap();
+        let s = streams.iter_mut().find(|s| s.id == stream_id).ok_or(AudioError::NotAvailable)?;
+        s.volume_01 = vol_01;
+        Ok(())
+    }
+
+    fn set_mute(&self, stream_id: u32, mute: bool) -> Result<(), AudioError> {
+        let mut streams = self.streams.lock().unwrap();
+        let s = streams.iter_mut().find(|s| s.id == stream_id).ok_or(AudioError::NotAvailable)?;
+        s.mute = mute;
+        Ok(())
+    }
+}
+
+fn drain(queue: &mut RequestQueue) -> Vec<String> {
+    std::iter::from_fn(|| queue.pop()).map(|r| format!("{:?}", r)).collect()
+}
+
+#[test]
+fn test_request_queue() {
+    let mut queue = RequestQueue::default();
+    queue.push(Request::SetVolume(1, 0.2));
+    queue.push(Request::SetMute(1, true));
+    queue.push(Request::List);
+    queue.push(Request::SetVolume(2, 0.5));
+    queue.push(Request::SetVolume(1, 0.3));
+    queue.push(Request::SetMute(1, false));
+    queue.push(Request::List);
+    assert_eq!(queue.len(), 4);
+    // the latest value in the first one's place, one listing after them all
+    assert_eq!(drain(&mut queue), vec!["SetVolume(1, 0.3)", "SetMute(1, false)", "SetVolume(2, 0.5)", "List"]);
+    assert!(queue.is_empty());
+
+    queue.push(Request::SetRules(RuleSet::default()));
+    queue.push(Request::List);
+    queue.push(Request::SetRules(RuleSet::default()));
+    assert_eq!(queue.len(), 2);
+}
+
+#[test]
+fn test_worker() {
+    let streams = vec![stream(1, "Firefox", 0.8, 0), stream(2, "mpv", 1.0, 0).with_binary("mpv")];
+    let backend = Arc::new(SharedBackend { streams: Mutex::new(streams) });
+    let rules = RuleSet::parse("[no mpv]\nbinary = mpv\nhide = yes\n").unwrap();
+    let (worker, replies) = BackendWorker::spawn(Arc::clone(&backend), RuleEngine::new(rules));
+
+    worker.send(Request::SetVolume(1, 0.3));
+    worker.send(Request::SetMute(2, true));
+    worker.send(Request::List);
+    let Reply::Streams(shown) = replies.recv_timeout(WAIT).unwrap() else { panic!("expected streams") };
+    assert_eq!(shown.len(), 1);
+    assert!((shown[0].volume_01 - 0.3).abs() < 0.001);
+    assert!(backend.streams.lock().unwrap()[1].mute);
+
+    worker.send(Request::SetVolume(9, 0.5));
+    let Reply::Failed { what, .. } = replies.recv_timeout(WAIT).unwrap() else { panic!("expected a failure") };
+    assert_eq!(what, "set volume of #9");
+
+    worker.send(Request::SetRules(RuleSet::default()));
+    worker.send(Request::List);
+    let Reply::Streams(shown) = replies.recv_timeout(WAIT).unwrap() else { panic!("expected streams") };
+    assert_eq!(shown.len(), 2);
+
+    // what is queued still goes out once the worker is dropped
+    worker.send(Request::SetVolume(2, 0.1));
+    drop(worker);
+    assert_eq!(replies.recv_timeout(WAIT).unwrap_err(), RecvTimeoutError::Disconnected);
+    assert!((backend.streams.lock().unwrap()[1].volume_01 - 0.1).abs() < 0.001);
+}
@@ -0,0 +1,73 @@
+use std::time::{Duration, Instant};
+
+use wlvolctl::writes::WriteCoordinator;
+
+const INTERVAL: Duration = Duration::from_millis(100);
+const SETTLE: Duration = Duration::from_millis(500);
+
+fn ms(n: u64) -> Duration {
+    Duration::from_millis(n)
+}
+
+#[test]
+fn test_throttle() {
+    let t0 = Instant::now();
+    let mut writes = WriteCoordinator::new(INTERVAL, SETTLE);
+
+    // the first value goes out at once, the ones right after wait
+    assert_eq!(writes.user_set(1, 0.1, t0), Some(0.1));
+    assert_eq!(writes.user_set(1, 0.2, t0 + ms(20)), None);
+    assert_eq!(writes.user_set(1, 0.3, t0 + ms(40)), None);
+    assert!(writes.due(t0 + ms(60)).is_empty());
+
+    // other streams have intervals of their own
+    assert_eq!(writes.user_set(2, 0.5, t0 + ms(50)), Some(0.5));
+
+    // only the last held back value is written
+    assert_eq!(writes.due(t0 + ms(100)), vec![(1, 0.3)]);
+    assert!(writes.due(t0 + ms(250)).is_empty());
+
+    // once the interval has passed, a value goes out directly
+    assert_eq!(writes.user_set(1, 0.4, t0 + ms(300)), Some(0.4));
+}
+
+#[test]
+fn test_final_value() {
+    let t0 = Instant::now();
+    let mut writes = WriteCoordinator::new(INTERVAL, SETTLE);
+
+    // a drag ending between two writes still sends where it stopped
+    let mut sent = Vec::new();
+    for (i, vol) in [0.1, 0.2, 0.3, 0.4, 0.5, 0.6].into_iter().enumerate() {
+        let now = t0 + ms(40 * i as u64);
+        sent.extend(writes.user_set(7, vol, now));
+        sent.extend(writes.due(now).into_iter().map(|(_, v)| v));
+    }
+    assert_eq!(sent, vec![0.1, 0.4]);
+    assert_eq!(writes.due(t0 + ms(220)), vec![(7, 0.6)]);
+}
+
+#[test]
+fn test_settled() {
+    let t0 = Instant::now();
+    let mut writes = WriteCoordinator::new(INTERVAL, SETTLE);
+
+    // streams never written are always settled
+    assert!(writes.settled(1, t0));
+
+    writes.user_set(1, 0.1, t0);
+    writes.user_set(1, 0.2, t0 + ms(50));
+    assert!(!writes.settled(1, t0 + ms(50)));
+    // a value still waiting keeps the stream unsettled
+    assert!(!writes.settled(1, t0 + ms(600)));
+
+    writes.due(t0 + ms(100));
+    assert!(!writes.settled(1, t0 + ms(599)));
+    assert!(writes.settled(1, t0 + ms(600)));
+    assert!(writes.settled(2, t0 + ms(100)));
+
+    // settled streams are forgotten, and write at once again
+    assert!(writes.due(t0 + ms(600)).is_empty());
+    assert!(writes.settled(1, t0 + ms(600)));
+    assert_eq!(writes.user_set(1, 0.3, t0 + ms(610)), Some(0.3));
+}